@@ -0,0 +1,124 @@
+//! Derive macros for arena-alloc, re-exported by it with its `derive` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Fields, GenericParam, Lifetime, LifetimeParam,
+};
+
+/// Derive `CloneIn` for a struct or enum by cloning each of its fields into the
+/// destination arena.
+///
+/// The copy is the same type with every lifetime parameter replaced by that of
+/// the destination, so `Node<'a>` is copied to a `Node<'b>`. Type parameters
+/// must copy to themselves, as `Copy` data such as integers does.
+#[proc_macro_derive(CloneIn)]
+pub fn derive_clone_in(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    clone_in(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn clone_in(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let dst_lifetime = Lifetime::new("'__dst", Span::call_site());
+
+    let mut impl_generics = input.generics.clone();
+    impl_generics.params.insert(
+        0,
+        GenericParam::Lifetime(LifetimeParam::new(dst_lifetime.clone())),
+    );
+    let where_clause = impl_generics.make_where_clause();
+    for param in &input.generics.params {
+        if let GenericParam::Type(param) = param {
+            let ident = &param.ident;
+            where_clause.predicates.push(syn::parse_quote! {
+                #ident: ::arena_alloc::CloneIn<#dst_lifetime, Cloned = #ident> + #dst_lifetime
+            });
+        }
+    }
+    let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let cloned_args = input.generics.params.iter().map(|param| match param {
+        GenericParam::Lifetime(_) => quote!(#dst_lifetime),
+        GenericParam::Type(param) => {
+            let ident = &param.ident;
+            quote!(#ident)
+        }
+        GenericParam::Const(param) => {
+            let ident = &param.ident;
+            quote!(#ident)
+        }
+    });
+    let cloned = if input.generics.params.is_empty() {
+        quote!(#name)
+    } else {
+        quote!(#name<#(#cloned_args),*>)
+    };
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let (pattern, construct) = clone_fields(quote!(#name), &data.fields);
+            quote! {
+                let #pattern = self;
+                ::core::option::Option::Some(#construct)
+            }
+        }
+        Data::Enum(data) => {
+            let arms = data.variants.iter().map(|variant| {
+                let ident = &variant.ident;
+                let (pattern, construct) = clone_fields(quote!(#name::#ident), &variant.fields);
+                quote!(#pattern => #construct,)
+            });
+            quote! {
+                ::core::option::Option::Some(match self {
+                    #(#arms)*
+                })
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new_spanned(
+                data.union_token,
+                "CloneIn can not be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::arena_alloc::CloneIn<#dst_lifetime> for #name #ty_generics #where_clause {
+            type Cloned = #cloned;
+
+            fn clone_in(
+                &self,
+                dst: &#dst_lifetime ::arena_alloc::DynArena,
+            ) -> ::core::option::Option<Self::Cloned> {
+                #body
+            }
+        }
+    })
+}
+
+/// A pattern binding every field of `path`, and an expression building it again
+/// from a copy of each field, returning None from the function if one fails.
+fn clone_fields(path: TokenStream2, fields: &Fields) -> (TokenStream2, TokenStream2) {
+    let bindings: Vec<_> = (0..fields.len())
+        .map(|i| format_ident!("__field{}", i))
+        .collect();
+    let copies = bindings
+        .iter()
+        .map(|binding| quote!(::arena_alloc::CloneIn::clone_in(#binding, dst)?));
+    match fields {
+        Fields::Named(fields) => {
+            let names: Vec<_> = fields.named.iter().map(|field| &field.ident).collect();
+            (
+                quote!(#path { #(#names: #bindings),* }),
+                quote!(#path { #(#names: #copies),* }),
+            )
+        }
+        Fields::Unnamed(_) => (quote!(#path(#(#bindings),*)), quote!(#path(#(#copies),*))),
+        Fields::Unit => (quote!(#path), quote!(#path)),
+    }
+}
@@ -0,0 +1,47 @@
+//! Values and slices aligned more strictly than their types need.
+
+use core::mem::{align_of, size_of};
+
+use crate::{DynArena, TypeInfo};
+
+impl DynArena {
+    /// acquire a reference to a value of type T that is initialized with the given value,
+    /// aligned to at least ALIGN bytes whatever the alignment of T, e.g. 32 or 64 for
+    /// SIMD kernels or to give a value a cache line of its own.
+    /// ALIGN must be a power of two.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_aligned<T, const ALIGN: usize>(&self, val: T) -> Option<&T> {
+        const { assert!(ALIGN.is_power_of_two(), "ALIGN must be a power of two") };
+        let align = ALIGN.max(align_of::<T>());
+        let mut slot = self.claim_drop_slot_for::<T>()?;
+        let place = self.reserve(size_of::<T>(), align);
+        #[cfg(feature = "record")]
+        self.record_layout(
+            core::any::type_name::<T>(),
+            size_of::<T>(),
+            align,
+            place.is_some(),
+        );
+        let place = place?;
+
+        let ptr = unsafe { self.store().cast::<u8>().add(place).cast::<T>() };
+        unsafe { ptr.write(val) };
+        slot.fill(place, TypeInfo::of::<T>());
+        Some(unsafe { &*ptr })
+    }
+
+    /// acquire a slice of `len` values of type T whose first value is aligned to at
+    /// least ALIGN bytes, each initialized with the result of calling `f` with its index.
+    /// ALIGN must be a power of two.
+    /// If `f` panics, the values it has already returned are dropped.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_slice_aligned_fill_with<T, const ALIGN: usize>(
+        &self,
+        len: usize,
+        mut f: impl FnMut(usize) -> T,
+    ) -> Option<&[T]> {
+        const { assert!(ALIGN.is_power_of_two(), "ALIGN must be a power of two") };
+        self.acquire_slice_aligned_try_fill_with(len, ALIGN, |i| Some(f(i)))
+            .map(|slice| &*slice)
+    }
+}
@@ -0,0 +1,76 @@
+//! A trait over the different kinds of arena, for code that should work with any of them.
+
+use crate::{Arena, DynArena, Init, RegionArena, Stats};
+
+/// The acquisition methods shared by every kind of arena, so that drivers and
+/// libraries can be written against any arena, or a mock of one, in tests.
+pub trait ArenaAlloc {
+    /// acquire a reference to a value of type T that is initialized with the given value.
+    fn acquire<T>(&self, val: T) -> Option<&T>;
+
+    /// acquire a reference to a value of type T that can be initialized with
+    /// the Init trait, using a given InitArg.
+    fn acquire_init<T: Init>(&self, arg: T::InitArg) -> Option<&T>;
+
+    /// acquire a reference to a value of type T that is initialized with the given value,
+    /// handing the value back if the arena has no room for it.
+    fn try_acquire<T>(&self, val: T) -> Result<&T, T>;
+
+    /// Take a snapshot of how much of the arena is in use.
+    fn stats(&self) -> Stats;
+}
+
+/// Implement [`ArenaAlloc`] for a [`DynArena`] or a type that derefs to one.
+macro_rules! deref_arena_alloc {
+    ($({$($generics:tt)*} $ty:ty),* $(,)?) => {$(
+        impl<$($generics)*> ArenaAlloc for $ty {
+            #[cfg_attr(feature = "record", track_caller)]
+            fn acquire<T>(&self, val: T) -> Option<&T> {
+                DynArena::acquire(self, val)
+            }
+
+            #[cfg_attr(feature = "record", track_caller)]
+            fn acquire_init<T: Init>(&self, arg: T::InitArg) -> Option<&T> {
+                DynArena::acquire_init(self, arg)
+            }
+
+            #[cfg_attr(feature = "record", track_caller)]
+            fn try_acquire<T>(&self, val: T) -> Result<&T, T> {
+                DynArena::try_acquire(self, val)
+            }
+
+            fn stats(&self) -> Stats {
+                DynArena::stats(self)
+            }
+        }
+    )*};
+}
+
+deref_arena_alloc!({} DynArena, {const SIZE: usize} Arena<SIZE>, {} RegionArena<'_>);
+
+#[cfg(feature = "guard-pages")]
+deref_arena_alloc!({const SIZE: usize} crate::GuardedArena<SIZE>);
+
+#[cfg(feature = "std")]
+deref_arena_alloc!({const SIZE: usize} crate::LockedArena<SIZE>);
+
+impl<A: ArenaAlloc + ?Sized> ArenaAlloc for &A {
+    #[cfg_attr(feature = "record", track_caller)]
+    fn acquire<T>(&self, val: T) -> Option<&T> {
+        (**self).acquire(val)
+    }
+
+    #[cfg_attr(feature = "record", track_caller)]
+    fn acquire_init<T: Init>(&self, arg: T::InitArg) -> Option<&T> {
+        (**self).acquire_init(arg)
+    }
+
+    #[cfg_attr(feature = "record", track_caller)]
+    fn try_acquire<T>(&self, val: T) -> Result<&T, T> {
+        (**self).try_acquire(val)
+    }
+
+    fn stats(&self) -> Stats {
+        (**self).stats()
+    }
+}
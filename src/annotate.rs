@@ -0,0 +1,141 @@
+//! Hooks that tell AddressSanitizer and Valgrind which parts of an arena are allocated.
+//!
+//! With the `asan` feature, unallocated space is poisoned so that touching it is reported.
+//! With the `valgrind` feature, each arena is registered as a Memcheck mempool and each
+//! acquisition as a chunk of it. Without either feature every hook compiles to nothing.
+
+// Each hook only uses the arguments its enabled tools need.
+#![allow(unused_variables)]
+#![cfg_attr(not(any(feature = "asan", feature = "valgrind")), allow(dead_code))]
+
+#[cfg(feature = "asan")]
+mod asan {
+    use core::ffi::c_void;
+
+    extern "C" {
+        fn __asan_poison_memory_region(addr: *const c_void, size: usize);
+        fn __asan_unpoison_memory_region(addr: *const c_void, size: usize);
+    }
+
+    pub fn poison(addr: *const u8, len: usize) {
+        unsafe { __asan_poison_memory_region(addr.cast(), len) };
+    }
+
+    pub fn unpoison(addr: *const u8, len: usize) {
+        unsafe { __asan_unpoison_memory_region(addr.cast(), len) };
+    }
+}
+
+#[cfg(feature = "valgrind")]
+mod valgrind {
+    const CREATE_MEMPOOL: usize = 0x1303;
+    const DESTROY_MEMPOOL: usize = 0x1304;
+    const MEMPOOL_ALLOC: usize = 0x1305;
+    const MEMPOOL_TRIM: usize = 0x1307;
+    const MEMPOOL_CHANGE: usize = 0x1308;
+    const MAKE_MEM_NOACCESS: usize = 0x4d43_0000;
+    const MAKE_MEM_UNDEFINED: usize = 0x4d43_0001;
+
+    /// Issue a Valgrind client request. Outside of Valgrind this does nothing.
+    #[cfg(target_arch = "x86_64")]
+    fn request(args: [usize; 6]) {
+        unsafe {
+            core::arch::asm!(
+                "rol rdi, 3",
+                "rol rdi, 13",
+                "rol rdi, 61",
+                "rol rdi, 51",
+                "xchg rbx, rbx",
+                in("rax") args.as_ptr(),
+                inout("rdx") 0usize => _,
+                options(nostack, preserves_flags),
+            );
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    fn request(_args: [usize; 6]) {}
+
+    pub fn create_pool(pool: *const u8, len: usize) {
+        request([CREATE_MEMPOOL, pool.addr(), 0, 0, 0, 0]);
+        request([MAKE_MEM_NOACCESS, pool.addr(), len, 0, 0, 0]);
+    }
+
+    pub fn alloc(pool: *const u8, addr: *const u8, len: usize) {
+        request([MEMPOOL_ALLOC, pool.addr(), addr.addr(), len, 0, 0]);
+    }
+
+    pub fn change(pool: *const u8, addr: *const u8, len: usize) {
+        request([MEMPOOL_CHANGE, pool.addr(), addr.addr(), addr.addr(), len, 0]);
+    }
+
+    pub fn undefined(addr: *const u8, len: usize) {
+        request([MAKE_MEM_UNDEFINED, addr.addr(), len, 0, 0, 0]);
+    }
+
+    pub fn trim(pool: *const u8, keep: usize, len: usize) {
+        request([MEMPOOL_TRIM, pool.addr(), pool.addr(), keep, 0, 0]);
+        request([
+            MAKE_MEM_NOACCESS,
+            pool.wrapping_add(keep).addr(),
+            len - keep,
+            0,
+            0,
+            0,
+        ]);
+    }
+
+    pub fn destroy_pool(pool: *const u8) {
+        request([DESTROY_MEMPOOL, pool.addr(), 0, 0, 0, 0]);
+    }
+}
+
+/// Mark a store of `len` bytes at `base` as entirely unallocated, before its first acquisition.
+pub fn start(base: *const u8, len: usize) {
+    #[cfg(feature = "asan")]
+    asan::poison(base, len);
+    #[cfg(feature = "valgrind")]
+    valgrind::create_pool(base, len);
+}
+
+/// Mark `len` bytes at `addr` in the store at `base` as allocated.
+pub fn reserve(base: *const u8, addr: *const u8, len: usize) {
+    #[cfg(feature = "asan")]
+    asan::unpoison(addr, len);
+    #[cfg(feature = "valgrind")]
+    valgrind::alloc(base, addr, len);
+}
+
+/// Let `len` unallocated bytes at `addr` be accessed, until they are allocated or the store is rewound.
+pub fn expose(addr: *const u8, len: usize) {
+    #[cfg(feature = "asan")]
+    asan::unpoison(addr, len);
+    #[cfg(feature = "valgrind")]
+    valgrind::undefined(addr, len);
+}
+
+/// Mark all but the first `keep` of the `len` bytes allocated at `addr` in the store
+/// at `base` as unallocated again.
+pub fn shrink(base: *const u8, addr: *const u8, len: usize, keep: usize) {
+    #[cfg(feature = "asan")]
+    asan::poison(addr.wrapping_add(keep), len - keep);
+    #[cfg(feature = "valgrind")]
+    valgrind::change(base, addr, keep);
+}
+
+/// Mark all but the first `keep` bytes of a store of `len` bytes at `base` as
+/// unallocated again, after a reset or the end of a scope.
+pub fn rewind(base: *const u8, keep: usize, len: usize) {
+    #[cfg(feature = "asan")]
+    asan::poison(base.wrapping_add(keep), len - keep);
+    #[cfg(feature = "valgrind")]
+    valgrind::trim(base, keep, len);
+}
+
+/// Hand a store of `len` bytes at `base` back as ordinary memory, when its arena is dropped.
+pub fn finish(base: *const u8, len: usize) {
+    #[cfg(feature = "asan")]
+    asan::unpoison(base, len);
+    #[cfg(feature = "valgrind")]
+    valgrind::destroy_pool(base);
+}
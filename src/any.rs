@@ -0,0 +1,84 @@
+//! Type-erased values, and a registry that finds them again by type.
+
+use core::any::{Any, TypeId};
+
+use crate::DynArena;
+
+impl DynArena {
+    /// acquire a reference to a value of type T that is initialized with the given value,
+    /// with its type erased. Downcast it to get the T back.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_any<T: Any>(&self, val: T) -> Option<&dyn Any> {
+        self.acquire(val).map(|val| val as &dyn Any)
+    }
+}
+
+/// A table of up to N values of different types kept in an arena, one per type,
+/// such as the drivers or plugins of a system, looked up by their type.
+pub struct TypeRegistry<'a, const N: usize> {
+    entries: [Option<(TypeId, &'a dyn Any)>; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for TypeRegistry<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, const N: usize> TypeRegistry<'a, N> {
+    /// Create a new registry with room for N types.
+    #[must_use]
+    pub const fn new() -> Self {
+        TypeRegistry {
+            entries: [None; N],
+            len: 0,
+        }
+    }
+
+    /// The number of types registered.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no types have been registered.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// acquire `val` from `arena` and register it as the value of type T.
+    /// Gives `val` back if a T is already registered, the registry is full,
+    /// or the arena is out of space.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn register<T: Any>(&mut self, arena: &'a DynArena, val: T) -> Result<&'a T, T> {
+        if self.contains::<T>() || self.len == N {
+            return Err(val);
+        }
+        let val = arena.try_acquire(val)?;
+        self.entries[self.len] = Some((TypeId::of::<T>(), val));
+        self.len += 1;
+        Ok(val)
+    }
+
+    /// The value registered as type T, if any.
+    pub fn get<T: Any>(&self) -> Option<&'a T> {
+        self.entries[..self.len]
+            .iter()
+            .flatten()
+            .find(|(id, _)| *id == TypeId::of::<T>())
+            .and_then(|(_, val)| val.downcast_ref())
+    }
+
+    /// Whether a value of type T is registered.
+    pub fn contains<T: Any>(&self) -> bool {
+        self.get::<T>().is_some()
+    }
+
+    /// Every registered value, in the order they were registered.
+    pub fn iter(&self) -> impl Iterator<Item = &'a dyn Any> + '_ {
+        self.entries[..self.len]
+            .iter()
+            .flatten()
+            .map(|(_, val)| *val)
+    }
+}
@@ -0,0 +1,91 @@
+//! rkyv archives built in and read from an arena, e.g. for configuration blobs
+//! persisted to flash.
+
+use core::{error::Error, fmt};
+
+use rkyv::{
+    api::low::{self, LowSerializer, LowValidator},
+    bytecheck::CheckBytes,
+    rancor::Source,
+    ser::{allocator::SubAllocator, writer::Buffer},
+    Portable, Serialize,
+};
+
+use crate::{atomic::Ordering, DynArena};
+
+/// The alignment archives are placed with, that of `rkyv::util::Align`, which
+/// is enough for any archived type.
+const ARCHIVE_ALIGN: usize = 16;
+
+/// Why an archive could not be acquired, when the arena is too full to hold it.
+#[derive(Debug)]
+struct OutOfSpace;
+
+impl fmt::Display for OutOfSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("arena is out of space")
+    }
+}
+
+impl Error for OutOfSpace {}
+
+impl DynArena {
+    /// acquire the bytes of `value` archived with rkyv, serializing it straight into
+    /// the arena. The last `scratch` bytes of the arena's spare capacity are lent to
+    /// the serializer for the temporary space some types need to be archived, and
+    /// handed back with the rest of the room the archive does not use.
+    ///
+    /// The bytes are aligned for any archived type, so they can be given to
+    /// `rkyv::access` as they are.
+    pub fn acquire_archive<E: Source>(
+        &self,
+        value: &impl for<'w> Serialize<LowSerializer<Buffer<'w>, SubAllocator<'w>, E>>,
+        scratch: usize,
+    ) -> Result<&[u8], E> {
+        let used = self.next_free_store_spot.load(Ordering::Acquire);
+        let start = unsafe { self.store().cast::<u8>().add(used) };
+        let room = self
+            .spare_capacity()
+            .checked_sub(start.align_offset(ARCHIVE_ALIGN))
+            .ok_or_else(|| E::new(OutOfSpace))?;
+        let out = room
+            .checked_sub(scratch)
+            .ok_or_else(|| E::new(OutOfSpace))?;
+        let mut window = self
+            .begin_write_aligned(room, ARCHIVE_ALIGN)
+            .ok_or_else(|| E::new(OutOfSpace))?;
+
+        let (buf, scratch) = window.buf().split_at_mut(out);
+        let len =
+            low::to_bytes_in_with_alloc(value, Buffer::from(buf), SubAllocator::new(scratch))?
+                .len();
+        Ok(unsafe { window.commit(len) })
+    }
+
+    /// acquire a copy of the archive in `bytes`, which need not be aligned, e.g. as read
+    /// from flash, and access it as a T once it has been validated. If it is not valid,
+    /// the room of the copy is handed back.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_archived<T, E>(&self, bytes: &[u8]) -> Result<&T, E>
+    where
+        T: Portable + for<'v> CheckBytes<LowValidator<'v, E>>,
+        E: Source,
+    {
+        let place = self.reserve(bytes.len(), ARCHIVE_ALIGN);
+        #[cfg(feature = "record")]
+        self.record_layout(
+            core::any::type_name::<T>(),
+            bytes.len(),
+            ARCHIVE_ALIGN,
+            place.is_some(),
+        );
+        let place = place.ok_or_else(|| E::new(OutOfSpace))?;
+
+        let copy = unsafe {
+            let start = self.store().cast::<u8>().add(place);
+            start.copy_from_nonoverlapping(bytes.as_ptr(), bytes.len());
+            core::slice::from_raw_parts(start, bytes.len())
+        };
+        low::access::<T, E>(copy).inspect_err(|_| self.shrink(place, bytes.len(), 0))
+    }
+}
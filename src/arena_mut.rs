@@ -0,0 +1,263 @@
+//! An exclusive-access arena for single-owner workloads that do not need synchronization.
+
+use core::{
+    any::TypeId,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    mem::MaybeUninit,
+};
+
+use crate::{align_place, sealed::Offset, Init, MemSlice};
+
+struct Slot<const SIZE: usize, I> {
+    place: I,
+    type_id: TypeId,
+    drop_func: fn(*mut MemSlice<SIZE>),
+}
+
+/// A handle to a value of type T stored in an [`ArenaMut`], which keeps its
+/// index in an I.
+///
+/// Handles do not record which arena they came from. An arena checks a handle
+/// against the type of the value at its index, so a handle from another arena
+/// whose index holds a value of the same type there is not detected, and refers
+/// to that value.
+pub struct Handle<T, I = usize> {
+    index: I,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    /// The handle to the value at `index` of an arena.
+    pub(crate) const fn new(index: usize) -> Self {
+        Handle {
+            index,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The index of the value in its arena.
+    pub(crate) const fn index(self) -> usize {
+        self.index
+    }
+}
+
+impl<T, I: Offset> Clone for Handle<T, I> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, I: Offset> Copy for Handle<T, I> {}
+
+impl<T, I: Offset> PartialEq for Handle<T, I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T, I: Offset> Eq for Handle<T, I> {}
+
+impl<T, I: Offset> Hash for Handle<T, I> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+impl<T, I: Offset> fmt::Debug for Handle<T, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Handle").field(&self.index).finish()
+    }
+}
+
+/// A fixed size arena that is owned by a single user.
+///
+/// All acquire methods take `&mut self`, so no atomics or interior mutability are needed.
+/// Because one acquisition can not be borrowed while making the next, values are
+/// accessed through the returned [`Handle`]s.
+///
+/// Values are placed at offsets aligned up to 16 bytes, the alignment of the backing
+/// store, so that they stay aligned when the arena is moved; acquiring a value that
+/// needs a greater alignment returns None.
+///
+/// Offsets into the arena and the indices of handles are kept in an I, which can
+/// be `u16` or `u32` for an arena small enough to shrink its slots and handles,
+/// e.g. `ArenaMut<4096, u16>`.
+pub struct ArenaMut<const SIZE: usize, I: Offset = usize> {
+    backing_store: MemSlice<SIZE>,
+    next_free_store_spot: usize,
+    slots: [Option<Slot<SIZE, I>>; SIZE],
+    next_free_slot: usize,
+}
+
+impl<const SIZE: usize, I: Offset> Default for ArenaMut<SIZE, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize, I: Offset> ArenaMut<SIZE, I> {
+    /// Create a new arena with a fixed size buffer of SIZE bytes.
+    /// Fails to compile if an I can not hold every offset into it.
+    #[must_use]
+    pub const fn new() -> Self {
+        const {
+            assert!(
+                SIZE == 0 || SIZE - 1 <= I::MAX,
+                "SIZE is too large for the offset type"
+            )
+        };
+        ArenaMut {
+            backing_store: MemSlice::new(),
+            next_free_store_spot: 0,
+            slots: [const { None }; SIZE],
+            next_free_slot: 0,
+        }
+    }
+
+    /// Get a place in the backing store where a value of type T can be placed.
+    /// Returns None if T needs a greater alignment than the backing store has.
+    fn get_ptr_place<T>(&mut self) -> Option<(usize, &mut MaybeUninit<T>)> {
+        let align = core::mem::align_of::<T>();
+        if self.next_free_slot == SIZE || align > core::mem::align_of::<MemSlice<SIZE>>() {
+            return None;
+        }
+        let store = self.backing_store.0.as_mut_ptr();
+        // Places are aligned as offsets rather than addresses, since the arena
+        // and the values in it may be moved between acquisitions.
+        let (place, end) = align_place(
+            0,
+            self.next_free_store_spot,
+            core::mem::size_of::<T>(),
+            align,
+            SIZE,
+        )?;
+        self.next_free_store_spot = end;
+
+        let ptr = unsafe { store.add(place).cast::<MaybeUninit<T>>().as_mut().unwrap() };
+
+        Some((place, ptr))
+    }
+
+    /// Record the value of type T at the given place and hand out its handle.
+    fn add_slot<T: 'static>(&mut self, place: usize) -> Handle<T, I> {
+        let index = self.next_free_slot;
+        self.slots[index] = Some(Slot {
+            place: I::from_usize(place),
+            type_id: TypeId::of::<T>(),
+            drop_func: |ptr: *mut MemSlice<SIZE>| unsafe {
+                ptr.cast::<T>().drop_in_place();
+            },
+        });
+        self.next_free_slot += 1;
+
+        Handle {
+            index: I::from_usize(index),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Find the place of the value a handle refers to, if it is a T in this arena.
+    fn place_of<T: 'static>(&self, handle: Handle<T, I>) -> Option<usize> {
+        let slot = self.slots.get(handle.index.to_usize())?.as_ref()?;
+        (slot.type_id == TypeId::of::<T>()).then_some(slot.place.to_usize())
+    }
+
+    /// acquire a handle to a value of type T that can be initialized with
+    /// the Init trait, using the default value of the InitArg.
+    pub fn acquire_init_default<T: Init + 'static>(&mut self) -> Option<Handle<T, I>>
+    where
+        T::InitArg: Default,
+    {
+        self.acquire_init(T::InitArg::default())
+    }
+
+    /// acquire a handle to a value of type T that can be initialized with
+    /// the Init trait, using a given InitArg.
+    pub fn acquire_init<T: Init + 'static>(&mut self, arg: T::InitArg) -> Option<Handle<T, I>> {
+        let (place, ptr) = self.get_ptr_place::<T>()?;
+
+        T::init(ptr, arg);
+
+        Some(self.add_slot::<T>(place))
+    }
+
+    /// acquire a handle to a value of type T that is initialized with it's default value.
+    pub fn acquire_default<T: Default + 'static>(&mut self) -> Option<Handle<T, I>> {
+        self.acquire(T::default())
+    }
+
+    /// acquire a handle to a value of type T that is initialized with the given value.
+    pub fn acquire<T: 'static>(&mut self, val: T) -> Option<Handle<T, I>> {
+        let (place, ptr) = self.get_ptr_place::<T>()?;
+
+        ptr.write(val);
+
+        Some(self.add_slot::<T>(place))
+    }
+
+    /// Move the value a handle refers to into `dst` and hand out its handle there,
+    /// e.g. to keep a result built in a scratch arena in a persistent one.
+    /// The value's room in this arena is not reused, and the old handle no longer
+    /// refers to anything. Returns None, leaving the value where it is, if the handle
+    /// refers to no T in this arena or `dst` is full.
+    pub fn transfer<T: 'static, const DST: usize, J: Offset>(
+        &mut self,
+        handle: Handle<T, I>,
+        dst: &mut ArenaMut<DST, J>,
+    ) -> Option<Handle<T, J>> {
+        let place = self.place_of(handle)?;
+        let (dst_place, ptr) = dst.get_ptr_place::<T>()?;
+
+        let src = unsafe { self.backing_store.0.as_ptr().add(place).cast::<T>() };
+        ptr.write(unsafe { src.read() });
+        self.slots[handle.index.to_usize()] = None;
+
+        Some(dst.add_slot::<T>(dst_place))
+    }
+
+    /// Get a reference to the value a handle refers to.
+    /// Returns None if the handle refers to no T in this arena.
+    pub fn get<T: 'static>(&self, handle: Handle<T, I>) -> Option<&T> {
+        let place = self.place_of(handle)?;
+        unsafe {
+            self.backing_store
+                .0
+                .as_ptr()
+                .add(place)
+                .cast::<T>()
+                .as_ref()
+        }
+    }
+
+    /// Get a mutable reference to the value a handle refers to.
+    /// Returns None if the handle refers to no T in this arena.
+    pub fn get_mut<T: 'static>(&mut self, handle: Handle<T, I>) -> Option<&mut T> {
+        let place = self.place_of(handle)?;
+        unsafe {
+            self.backing_store
+                .0
+                .as_mut_ptr()
+                .add(place)
+                .cast::<T>()
+                .as_mut()
+        }
+    }
+}
+
+impl<const SIZE: usize, I: Offset> Drop for ArenaMut<SIZE, I> {
+    fn drop(&mut self) {
+        for slot in &self.slots[..self.next_free_slot] {
+            // Slots of values transferred to another arena are empty.
+            let Some(Slot {
+                place, drop_func, ..
+            }) = slot
+            else {
+                continue;
+            };
+            let ptr = unsafe { (&raw mut self.backing_store).byte_add(place.to_usize()) };
+            drop_func(ptr);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Routing acquisitions between several arenas.
+
+use crate::DynArena;
+
+/// A set of N arenas, e.g. one in each RAM bank of an MCU, that acquisitions are
+/// routed between by free space.
+///
+/// Every acquisition tells which arena served it, by its index in the set.
+pub struct ArenaSet<'a, const N: usize> {
+    arenas: [&'a DynArena; N],
+}
+
+impl<'a, const N: usize> ArenaSet<'a, N> {
+    /// Create a set of `arenas`, which are told apart by their index in it.
+    #[must_use]
+    pub const fn new(arenas: [&'a DynArena; N]) -> Self {
+        ArenaSet { arenas }
+    }
+
+    /// The arenas of the set.
+    pub fn arenas(&self) -> &[&'a DynArena; N] {
+        &self.arenas
+    }
+
+    /// The number of bytes that have not been acquired yet, across the set.
+    pub fn spare_capacity(&self) -> usize {
+        self.arenas.iter().map(|arena| arena.spare_capacity()).sum()
+    }
+
+    /// acquire a reference to a value of type T that is initialized with the given value,
+    /// from the arena with the most free space that has room for it.
+    /// Returns the index of that arena with the reference.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire<T>(&self, val: T) -> Option<(usize, &'a T)> {
+        self.try_acquire_from(val, [false; N]).ok()
+    }
+
+    /// acquire a reference to a value of type T that is initialized with the given value,
+    /// from the arena at `hint` if it has room for it, e.g. the bank a DMA engine can
+    /// reach, and otherwise from the one with the most free space.
+    /// Returns the index of the arena that served it with the reference.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_near<T>(&self, hint: usize, val: T) -> Option<(usize, &'a T)> {
+        let mut tried = [false; N];
+        let val = match self.arenas.get(hint) {
+            Some(arena) => match arena.try_acquire(val) {
+                Ok(val) => return Some((hint, val)),
+                Err(val) => {
+                    tried[hint] = true;
+                    val
+                }
+            },
+            None => val,
+        };
+        self.try_acquire_from(val, tried).ok()
+    }
+
+    /// Try the arenas not yet `tried` from the most free space down, handing the
+    /// value back if none of them has room for it.
+    #[cfg_attr(feature = "record", track_caller)]
+    fn try_acquire_from<T>(&self, mut val: T, mut tried: [bool; N]) -> Result<(usize, &'a T), T> {
+        loop {
+            let Some(index) = (0..N)
+                .filter(|&i| !tried[i])
+                .max_by_key(|&i| self.arenas[i].spare_capacity())
+            else {
+                return Err(val);
+            };
+            match self.arenas[index].try_acquire(val) {
+                Ok(val) => return Ok((index, val)),
+                Err(back) => val = back,
+            }
+            tried[index] = true;
+        }
+    }
+}
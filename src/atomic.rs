@@ -0,0 +1,10 @@
+//! The atomics the arenas are built on: those of `core`, or with the `portable-atomic`
+//! feature those of the portable-atomic crate, for targets such as MSP430 and AVR
+//! that have no compare-and-swap.
+
+#[cfg(not(feature = "portable-atomic"))]
+#[allow(unused_imports)]
+pub(crate) use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU8, AtomicUsize, Ordering};
+#[cfg(feature = "portable-atomic")]
+#[allow(unused_imports)]
+pub(crate) use portable_atomic::{AtomicBool, AtomicPtr, AtomicU8, AtomicUsize, Ordering};
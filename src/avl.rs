@@ -0,0 +1,290 @@
+//! Intrusive balanced trees of values kept in an arena.
+
+use core::{borrow::Borrow, cell::Cell, cmp::Ordering};
+
+/// The most nodes on a path from the root of an [`AvlTree`] to a leaf, as an AVL
+/// tree of height h holds more than 1.6^h nodes.
+const MAX_HEIGHT: usize = usize::BITS as usize * 3 / 2;
+
+/// The links that put a value in an [`AvlTree`], embedded in the value itself.
+pub struct TreeLink<'a, T> {
+    left: Cell<Option<&'a T>>,
+    right: Cell<Option<&'a T>>,
+    // Zero while the value is in no tree.
+    height: Cell<u8>,
+}
+
+impl<T> Default for TreeLink<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> TreeLink<'_, T> {
+    /// Create the links of a value that is in no tree.
+    #[must_use]
+    pub const fn new() -> Self {
+        TreeLink {
+            left: Cell::new(None),
+            right: Cell::new(None),
+            height: Cell::new(0),
+        }
+    }
+
+    /// Whether the value is in a tree.
+    pub fn is_linked(&self) -> bool {
+        self.height.get() != 0
+    }
+}
+
+/// A value that can be put in an [`AvlTree`], such as a region of an address
+/// space or a timer, ordered by its key.
+pub trait TreeNode<'a>: Sized + 'a {
+    /// What the values of a tree are ordered and looked up by.
+    type Key: Ord;
+
+    /// The key of the value, which must not change while it is in a tree.
+    fn key(&self) -> &Self::Key;
+
+    /// The links embedded in the value.
+    fn link(&self) -> &TreeLink<'a, Self>;
+}
+
+fn height<'a, T: TreeNode<'a>>(node: Option<&'a T>) -> u8 {
+    node.map_or(0, |node| node.link().height.get())
+}
+
+fn update<'a, T: TreeNode<'a>>(node: &'a T) {
+    let link = node.link();
+    link.height
+        .set(1 + height(link.left.get()).max(height(link.right.get())));
+}
+
+fn rotate_right<'a, T: TreeNode<'a>>(node: &'a T) -> &'a T {
+    let Some(left) = node.link().left.get() else {
+        return node;
+    };
+    node.link().left.set(left.link().right.get());
+    left.link().right.set(Some(node));
+    update(node);
+    update(left);
+    left
+}
+
+fn rotate_left<'a, T: TreeNode<'a>>(node: &'a T) -> &'a T {
+    let Some(right) = node.link().right.get() else {
+        return node;
+    };
+    node.link().right.set(right.link().left.get());
+    right.link().left.set(Some(node));
+    update(node);
+    update(right);
+    right
+}
+
+/// Restore the balance of the subtree at `node` after one of its subtrees changed
+/// height by one, returning its new root.
+fn balance<'a, T: TreeNode<'a>>(node: &'a T) -> &'a T {
+    update(node);
+    let link = node.link();
+    let (left, right) = (link.left.get(), link.right.get());
+    if height(left) > height(right) + 1 {
+        if let Some(left) =
+            left.filter(|l| height(l.link().left.get()) < height(l.link().right.get()))
+        {
+            link.left.set(Some(rotate_left(left)));
+        }
+        rotate_right(node)
+    } else if height(right) > height(left) + 1 {
+        if let Some(right) =
+            right.filter(|r| height(r.link().right.get()) < height(r.link().left.get()))
+        {
+            link.right.set(Some(rotate_right(right)));
+        }
+        rotate_left(node)
+    } else {
+        node
+    }
+}
+
+/// Insert `node` into the subtree at `root`, returning its new root,
+/// or None if a value with the same key is already in it.
+fn insert<'a, T: TreeNode<'a>>(root: Option<&'a T>, node: &'a T) -> Option<&'a T> {
+    let Some(root) = root else {
+        node.link().height.set(1);
+        return Some(node);
+    };
+    let link = root.link();
+    match node.key().cmp(root.key()) {
+        Ordering::Less => link.left.set(Some(insert(link.left.get(), node)?)),
+        Ordering::Greater => link.right.set(Some(insert(link.right.get(), node)?)),
+        Ordering::Equal => return None,
+    }
+    Some(balance(root))
+}
+
+/// Remove the least value from the subtree at `root`, returning the new root and the value.
+fn remove_first<'a, T: TreeNode<'a>>(root: &'a T) -> (Option<&'a T>, &'a T) {
+    let link = root.link();
+    match link.left.get() {
+        None => (link.right.get(), root),
+        Some(left) => {
+            let (left, first) = remove_first(left);
+            link.left.set(left);
+            (Some(balance(root)), first)
+        }
+    }
+}
+
+/// Remove the value with `key` from the subtree at `root`, returning the new root
+/// and the value, if it was there.
+fn remove<'a, T: TreeNode<'a>, Q: Ord + ?Sized>(
+    root: Option<&'a T>,
+    key: &Q,
+) -> (Option<&'a T>, Option<&'a T>)
+where
+    T::Key: Borrow<Q>,
+{
+    let Some(root) = root else {
+        return (None, None);
+    };
+    let link = root.link();
+    let (side, removed) = match key.cmp(root.key().borrow()) {
+        Ordering::Less => (&link.left, remove(link.left.get(), key)),
+        Ordering::Greater => (&link.right, remove(link.right.get(), key)),
+        Ordering::Equal => {
+            let replacement = match (link.left.get(), link.right.get()) {
+                (None, right) => right,
+                (left, None) => left,
+                (left, Some(right)) => {
+                    let (right, first) = remove_first(right);
+                    first.link().left.set(left);
+                    first.link().right.set(right);
+                    Some(balance(first))
+                }
+            };
+            link.left.set(None);
+            link.right.set(None);
+            link.height.set(0);
+            return (replacement, Some(root));
+        }
+    };
+    match removed {
+        (subtree, Some(removed)) => {
+            side.set(subtree);
+            (Some(balance(root)), Some(removed))
+        }
+        (_, None) => (Some(root), None),
+    }
+}
+
+/// An AVL tree of values of type T kept in an arena, linked through the
+/// [`TreeLink`] embedded in each value, so building one acquires nothing more.
+///
+/// A value can be in one tree per link it embeds. Removing it unlinks it,
+/// so it can be inserted again.
+pub struct AvlTree<'a, T> {
+    root: Option<&'a T>,
+    len: usize,
+}
+
+impl<'a, T: TreeNode<'a>> Default for AvlTree<'a, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T: TreeNode<'a>> AvlTree<'a, T> {
+    /// Create a new, empty tree.
+    #[must_use]
+    pub const fn new() -> Self {
+        AvlTree { root: None, len: 0 }
+    }
+
+    /// The number of values in the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the tree holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Insert `node` into the tree.
+    /// Gives it back if it is already in a tree or a value with the same key is in this one.
+    pub fn insert(&mut self, node: &'a T) -> Result<(), &'a T> {
+        if node.link().is_linked() {
+            return Err(node);
+        }
+        self.root = Some(insert(self.root, node).ok_or(node)?);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Remove the value with `key` from the tree and return it, if it is there.
+    pub fn remove<Q: Ord + ?Sized>(&mut self, key: &Q) -> Option<&'a T>
+    where
+        T::Key: Borrow<Q>,
+    {
+        let (root, removed) = remove(self.root, key);
+        let removed = removed?;
+        self.root = root;
+        self.len -= 1;
+        Some(removed)
+    }
+
+    /// The value with `key`, if it is in the tree.
+    pub fn get<Q: Ord + ?Sized>(&self, key: &Q) -> Option<&'a T>
+    where
+        T::Key: Borrow<Q>,
+    {
+        let mut node = self.root;
+        while let Some(current) = node {
+            node = match key.cmp(current.key().borrow()) {
+                Ordering::Less => current.link().left.get(),
+                Ordering::Greater => current.link().right.get(),
+                Ordering::Equal => return Some(current),
+            };
+        }
+        None
+    }
+
+    /// The value with the least key, e.g. the timer that is due next.
+    pub fn first(&self) -> Option<&'a T> {
+        let mut node = self.root?;
+        while let Some(left) = node.link().left.get() {
+            node = left;
+        }
+        Some(node)
+    }
+
+    /// Remove the value with the least key from the tree and return it.
+    pub fn pop_first(&mut self) -> Option<&'a T> {
+        let (root, first) = remove_first(self.root?);
+        self.root = root;
+        self.len -= 1;
+        let link = first.link();
+        link.right.set(None);
+        link.height.set(0);
+        Some(first)
+    }
+
+    /// Every value in the tree, in order of their keys.
+    pub fn iter(&self) -> impl Iterator<Item = &'a T> + '_ {
+        let mut stack = [None; MAX_HEIGHT];
+        let mut depth = 0;
+        let mut next = self.root;
+        core::iter::from_fn(move || {
+            while let Some(node) = next {
+                stack[depth] = Some(node);
+                depth += 1;
+                next = node.link().left.get();
+            }
+            depth = depth.checked_sub(1)?;
+            let node: &'a T = stack[depth]?;
+            next = node.link().right.get();
+            Some(node)
+        })
+    }
+}
@@ -0,0 +1,111 @@
+//! Lifetime branded arenas whose references can not be mixed with those of another arena.
+
+use core::{fmt, marker::PhantomData, ops::Deref};
+
+use crate::{Arena, Init};
+
+/// An invariant lifetime that identifies a single branded arena.
+type Brand<'id> = PhantomData<fn(&'id ()) -> &'id ()>;
+
+/// An arena whose references carry the invariant brand `'id`.
+/// Created with [`Arena::branded`].
+pub struct Branded<'id, const SIZE: usize> {
+    arena: Arena<SIZE>,
+    _brand: Brand<'id>,
+}
+
+/// A reference to a value in the branded arena `'id`.
+///
+/// Because `'id` is invariant, a `BrandedRef` from one arena can not be stored
+/// where a `BrandedRef` of another arena is expected.
+pub struct BrandedRef<'id, T: ?Sized> {
+    inner: &'id T,
+    _brand: Brand<'id>,
+}
+
+impl<T: ?Sized> Clone for BrandedRef<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for BrandedRef<'_, T> {}
+
+impl<T: ?Sized> Deref for BrandedRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.inner
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for BrandedRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<'id, T: ?Sized> BrandedRef<'id, T> {
+    fn new(inner: &'id T) -> Self {
+        BrandedRef {
+            inner,
+            _brand: PhantomData,
+        }
+    }
+}
+
+impl<const SIZE: usize> Arena<SIZE> {
+    /// Create a new arena and pass it to `f` with a fresh brand.
+    /// References acquired from it can not be mixed with those of any other branded arena.
+    ///
+    /// ```compile_fail
+    /// use arena_alloc::{Arena, BrandedRef};
+    /// use std::cell::Cell;
+    ///
+    /// struct Node<'id> {
+    ///     next: Cell<Option<BrandedRef<'id, Node<'id>>>>,
+    /// }
+    ///
+    /// Arena::<100>::branded(|a| {
+    ///     Arena::<100>::branded(|b| {
+    ///         let from_a = a.acquire(Node { next: Cell::new(None) }).unwrap();
+    ///         let from_b = b.acquire(Node { next: Cell::new(None) }).unwrap();
+    ///         from_b.next.set(Some(from_a));
+    ///     });
+    /// });
+    /// ```
+    pub fn branded<R>(f: impl for<'id> FnOnce(&'id Branded<'id, SIZE>) -> R) -> R {
+        let branded = Branded {
+            arena: Arena::new(),
+            _brand: PhantomData,
+        };
+        f(&branded)
+    }
+}
+
+impl<'id, const SIZE: usize> Branded<'id, SIZE> {
+    /// acquire a branded reference to a value of type T that can be initialized with
+    /// the Init trait, using the default value of the InitArg.
+    pub fn acquire_init_default<T: Init>(&'id self) -> Option<BrandedRef<'id, T>>
+    where
+        T::InitArg: Default,
+    {
+        self.arena.acquire_init_default().map(BrandedRef::new)
+    }
+
+    /// acquire a branded reference to a value of type T that can be initialized with
+    /// the Init trait, using a given InitArg.
+    pub fn acquire_init<T: Init>(&'id self, arg: T::InitArg) -> Option<BrandedRef<'id, T>> {
+        self.arena.acquire_init(arg).map(BrandedRef::new)
+    }
+
+    /// acquire a branded reference to a value of type T that is initialized with it's default value.
+    pub fn acquire_default<T: Default>(&'id self) -> Option<BrandedRef<'id, T>> {
+        self.arena.acquire_default().map(BrandedRef::new)
+    }
+
+    /// acquire a branded reference to a value of type T that is initialized with the given value.
+    pub fn acquire<T>(&'id self, val: T) -> Option<BrandedRef<'id, T>> {
+        self.arena.acquire(val).map(BrandedRef::new)
+    }
+}
@@ -0,0 +1,339 @@
+//! An ordered map whose nodes are kept in an arena.
+
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ops::{Bound, RangeBounds},
+    ptr::{self, NonNull},
+    slice,
+};
+
+use crate::DynArena;
+
+/// The minimum number of children of a node other than the root.
+const B: usize = 4;
+/// The maximum number of keys in a node.
+const CAP: usize = 2 * B - 1;
+/// The most nodes on a path from the root to a leaf, as every node but the root
+/// has at least B children.
+const MAX_DEPTH: usize = usize::BITS as usize / 2;
+
+type Link<K, V> = Option<NonNull<Node<K, V>>>;
+
+struct Node<K, V> {
+    len: usize,
+    keys: [MaybeUninit<K>; CAP],
+    vals: [MaybeUninit<V>; CAP],
+    // All None for a leaf.
+    children: [Link<K, V>; CAP + 1],
+}
+
+impl<K, V> Node<K, V> {
+    fn new() -> Self {
+        Node {
+            len: 0,
+            keys: [const { MaybeUninit::uninit() }; CAP],
+            vals: [const { MaybeUninit::uninit() }; CAP],
+            children: [None; CAP + 1],
+        }
+    }
+
+    fn keys(&self) -> &[K] {
+        unsafe { slice::from_raw_parts(self.keys.as_ptr().cast(), self.len) }
+    }
+
+    fn vals(&self) -> &[V] {
+        unsafe { slice::from_raw_parts(self.vals.as_ptr().cast(), self.len) }
+    }
+
+    fn vals_mut(&mut self) -> &mut [V] {
+        unsafe { slice::from_raw_parts_mut(self.vals.as_mut_ptr().cast(), self.len) }
+    }
+
+    /// Where `key` is among the keys of the node, or where it would go.
+    fn search<Q: Ord + ?Sized>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+    {
+        self.keys().binary_search_by(|k| k.borrow().cmp(key))
+    }
+
+    /// Put `key` and `val` at `i`, shifting the keys and values after it along.
+    /// The node must not be full.
+    fn insert(&mut self, i: usize, key: K, val: V) {
+        unsafe {
+            let keys = self.keys.as_mut_ptr().add(i);
+            ptr::copy(keys, keys.add(1), self.len - i);
+            keys.write(MaybeUninit::new(key));
+            let vals = self.vals.as_mut_ptr().add(i);
+            ptr::copy(vals, vals.add(1), self.len - i);
+            vals.write(MaybeUninit::new(val));
+        }
+        self.len += 1;
+    }
+}
+
+impl<K, V> Drop for Node<K, V> {
+    // Children are values of the arena in their own right, so the arena drops them.
+    fn drop(&mut self) {
+        unsafe {
+            ptr::slice_from_raw_parts_mut(self.keys.as_mut_ptr().cast::<K>(), self.len)
+                .drop_in_place();
+            ptr::slice_from_raw_parts_mut(self.vals.as_mut_ptr().cast::<V>(), self.len)
+                .drop_in_place();
+        }
+    }
+}
+
+/// Split the full child at `i` of `parent` in two around its middle key, which
+/// moves up into `parent`. `parent` must not be full.
+/// Returns None, changing nothing, if the arena has no room for the new node.
+#[cfg_attr(feature = "record", track_caller)]
+fn split_child<K, V>(arena: &DynArena, parent: &mut Node<K, V>, i: usize) -> Option<()> {
+    let sibling = arena.try_acquire_mut(Node::new()).ok()?;
+    let child = unsafe { parent.children[i]?.as_mut() };
+    let (key, val) = unsafe {
+        ptr::copy_nonoverlapping(child.keys.as_ptr().add(B), sibling.keys.as_mut_ptr(), B - 1);
+        ptr::copy_nonoverlapping(child.vals.as_ptr().add(B), sibling.vals.as_mut_ptr(), B - 1);
+        (
+            child.keys[B - 1].assume_init_read(),
+            child.vals[B - 1].assume_init_read(),
+        )
+    };
+    sibling.children[..B].copy_from_slice(&child.children[B..]);
+    child.children[B..].fill(None);
+    sibling.len = B - 1;
+    child.len = B - 1;
+
+    parent.children.copy_within(i + 1..=parent.len, i + 2);
+    parent.children[i + 1] = Some(NonNull::from(sibling));
+    parent.insert(i, key, val);
+    Some(())
+}
+
+/// A map from keys of type K to values of type V, ordered by key, whose nodes
+/// are acquired from an arena as it grows, e.g. a routing table or a schedule
+/// that is looked up by ranges of keys.
+///
+/// The space of a map is only reclaimed when its arena is reset.
+pub struct ArenaBTreeMap<'a, K, V> {
+    arena: &'a DynArena,
+    root: Link<K, V>,
+    len: usize,
+    _marker: PhantomData<&'a mut Node<K, V>>,
+}
+
+unsafe impl<K: Send, V: Send> Send for ArenaBTreeMap<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for ArenaBTreeMap<'_, K, V> {}
+
+impl<'a, K: Ord, V> ArenaBTreeMap<'a, K, V> {
+    /// Create a new, empty map whose nodes are acquired from `arena`.
+    #[must_use]
+    pub const fn new(arena: &'a DynArena) -> Self {
+        ArenaBTreeMap {
+            arena,
+            root: None,
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The value of `key`, if it is in the map.
+    pub fn get<Q: Ord + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        let mut node = unsafe { self.root?.as_ref() };
+        loop {
+            match node.search(key) {
+                Ok(i) => return Some(&node.vals()[i]),
+                Err(i) => node = unsafe { node.children[i]?.as_ref() },
+            }
+        }
+    }
+
+    /// A mutable reference to the value of `key`, if it is in the map.
+    pub fn get_mut<Q: Ord + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        let mut node = unsafe { self.root?.as_mut() };
+        loop {
+            match node.search(key) {
+                Ok(i) => return Some(&mut node.vals_mut()[i]),
+                Err(i) => node = unsafe { node.children[i]?.as_mut() },
+            }
+        }
+    }
+
+    /// Whether `key` is in the map.
+    pub fn contains_key<Q: Ord + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Map `key` to `val`, returning the value it was mapped to before, if any.
+    /// Gives `key` and `val` back if the arena has no room for the nodes it needs.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn insert(&mut self, key: K, val: V) -> Result<Option<V>, (K, V)> {
+        let arena = self.arena;
+        let mut node = match self.root {
+            Some(mut root) => unsafe { root.as_mut() },
+            None => {
+                let Ok(root) = arena.try_acquire_mut(Node::new()) else {
+                    return Err((key, val));
+                };
+                // The node is reached through the stored pointer from here on, so
+                // that the pointer stays valid as it is written to.
+                let mut root = NonNull::from(root);
+                self.root = Some(root);
+                unsafe { root.as_mut() }
+            }
+        };
+        if node.len == CAP {
+            let Ok(root) = arena.try_acquire_mut(Node::new()) else {
+                return Err((key, val));
+            };
+            let mut root = NonNull::from(root);
+            let new_root = unsafe { root.as_mut() };
+            new_root.children[0] = self.root;
+            if split_child(arena, new_root, 0).is_none() {
+                return Err((key, val));
+            }
+            self.root = Some(root);
+            node = new_root;
+        }
+        // Full nodes are split on the way down, so there is always room for a key
+        // to move up into the parent.
+        loop {
+            let mut i = match node.search(&key) {
+                Ok(i) => return Ok(Some(mem::replace(&mut node.vals_mut()[i], val))),
+                Err(i) => i,
+            };
+            let Some(child) = node.children[i] else {
+                node.insert(i, key, val);
+                self.len += 1;
+                return Ok(None);
+            };
+            if unsafe { child.as_ref() }.len == CAP {
+                if split_child(arena, node, i).is_none() {
+                    return Err((key, val));
+                }
+                match key.cmp(&node.keys()[i]) {
+                    Ordering::Equal => {
+                        return Ok(Some(mem::replace(&mut node.vals_mut()[i], val)));
+                    }
+                    Ordering::Greater => i += 1,
+                    Ordering::Less => {}
+                }
+            }
+            node = unsafe { node.children[i].unwrap_unchecked().as_mut() };
+        }
+    }
+
+    /// The entries whose keys are in `range`, in order.
+    pub fn range<Q, R>(&self, range: R) -> impl Iterator<Item = (&K, &V)> + use<'_, K, V, Q, R>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let mut iter = Range {
+            stack: [(None, 0); MAX_DEPTH],
+            depth: 0,
+            range,
+            _marker: PhantomData,
+            _query: PhantomData,
+        };
+        let mut next = self.root;
+        while let Some(node) = next {
+            let node_ref = unsafe { node.as_ref() };
+            let i = match iter.range.start_bound() {
+                Bound::Included(start) => match node_ref.search(start) {
+                    // Everything below the key is before the start.
+                    Ok(i) => {
+                        iter.push(node, i);
+                        break;
+                    }
+                    Err(i) => i,
+                },
+                Bound::Excluded(start) => node_ref.search(start).map_or_else(|i| i, |i| i + 1),
+                Bound::Unbounded => 0,
+            };
+            iter.push(node, i);
+            next = node_ref.children[i];
+        }
+        iter
+    }
+
+    /// Every entry, in order of their keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.range::<K, _>(..)
+    }
+}
+
+/// The entries of an [`ArenaBTreeMap`] in a range.
+struct Range<'m, K, V, Q: ?Sized, R> {
+    // The path to the next entry: for each node on it, the index of its next key.
+    stack: [(Link<K, V>, usize); MAX_DEPTH],
+    depth: usize,
+    range: R,
+    _marker: PhantomData<&'m Node<K, V>>,
+    _query: PhantomData<fn(&Q)>,
+}
+
+impl<K, V, Q: ?Sized, R> Range<'_, K, V, Q, R> {
+    fn push(&mut self, node: NonNull<Node<K, V>>, i: usize) {
+        self.stack[self.depth] = (Some(node), i);
+        self.depth += 1;
+    }
+}
+
+impl<'m, K: Borrow<Q>, V, Q: Ord + ?Sized, R: RangeBounds<Q>> Iterator for Range<'m, K, V, Q, R> {
+    type Item = (&'m K, &'m V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.depth > 0 {
+            let (Some(node), i) = self.stack[self.depth - 1] else {
+                return None;
+            };
+            let node_ref: &'m Node<K, V> = unsafe { node.as_ref() };
+            if i == node_ref.len {
+                self.depth -= 1;
+                continue;
+            }
+            let key = &node_ref.keys()[i];
+            let past_end = match self.range.end_bound() {
+                Bound::Included(end) => key.borrow() > end,
+                Bound::Excluded(end) => key.borrow() >= end,
+                Bound::Unbounded => false,
+            };
+            if past_end {
+                self.depth = 0;
+                return None;
+            }
+            self.stack[self.depth - 1] = (Some(node), i + 1);
+            let mut next = node_ref.children[i + 1];
+            while let Some(child) = next {
+                self.push(child, 0);
+                next = unsafe { child.as_ref() }.children[0];
+            }
+            return Some((key, &node_ref.vals()[i]));
+        }
+        None
+    }
+}
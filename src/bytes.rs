@@ -0,0 +1,65 @@
+//! Byte buffers that skip the drop queue.
+//!
+//! Bytes need no dropping and no alignment, so acquiring them takes one step on
+//! the cursor of the arena and nothing else, which suits the temporary buffers
+//! of protocol code.
+
+use core::slice;
+
+use crate::DynArena;
+
+impl DynArena {
+    /// acquire a buffer of `len` zeroed bytes.
+    // Every call reserves a disjoint region of the backing store, so handing out
+    // a mutable reference to it from a shared borrow of the arena is sound.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_bytes(&self, len: usize) -> Option<&mut [u8]> {
+        let bytes = self.reserve_bytes_ptr(len)?;
+        unsafe { bytes.write_bytes(0, len) };
+        Some(unsafe { slice::from_raw_parts_mut(bytes, len) })
+    }
+
+    /// acquire a buffer holding a copy of `bytes`.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_bytes_copy(&self, bytes: &[u8]) -> Option<&mut [u8]> {
+        let copy = self.reserve_bytes_ptr(bytes.len())?;
+        unsafe { copy.copy_from_nonoverlapping(bytes.as_ptr(), bytes.len()) };
+        Some(unsafe { slice::from_raw_parts_mut(copy, bytes.len()) })
+    }
+
+    /// A handle to the arena that only acquires byte buffers.
+    pub fn bytes(&self) -> ByteArena<'_> {
+        ByteArena { arena: self }
+    }
+
+    #[cfg_attr(feature = "record", track_caller)]
+    fn reserve_bytes_ptr(&self, len: usize) -> Option<*mut u8> {
+        let place = self.reserve_bytes(len);
+        #[cfg(feature = "record")]
+        self.record_layout(core::any::type_name::<[u8]>(), len, 1, place.is_some());
+        Some(unsafe { self.store().cast::<u8>().add(place?) })
+    }
+}
+
+/// A handle to an arena for code that only needs scratch bytes, such as the
+/// encoder of a wire format.
+#[derive(Clone, Copy)]
+pub struct ByteArena<'a> {
+    arena: &'a DynArena,
+}
+
+impl<'a> ByteArena<'a> {
+    /// acquire a buffer of `len` zeroed bytes.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire(&self, len: usize) -> Option<&'a mut [u8]> {
+        self.arena.acquire_bytes(len)
+    }
+
+    /// acquire a buffer holding a copy of `bytes`.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_copy(&self, bytes: &[u8]) -> Option<&'a mut [u8]> {
+        self.arena.acquire_bytes_copy(bytes)
+    }
+}
@@ -0,0 +1,59 @@
+//! Canary bytes after every allocation, for catching writes past the end of a
+//! value into the one after it.
+
+use core::mem::size_of;
+
+use crate::{atomic::Ordering, DynArena};
+
+/// The bytes a canary starts with, which a write past the end of the value before
+/// it is likely to change.
+const PATTERN: [u8; 8] = *b"\xca\xfe\xf0\x0d\xde\xad\xbe\xef";
+
+/// The size of a canary: the pattern, then the offset of the end of the previous
+/// reservation, which chains the canaries together from the cursor back.
+pub(crate) const CANARY: usize = PATTERN.len() + size_of::<usize>();
+
+impl DynArena {
+    /// Check the canary after every allocation in the arena, returning the offset of
+    /// the first one found overwritten, which is the end of the value that overran.
+    ///
+    /// Also run when the arena is dropped, which panics if one was overwritten. An
+    /// allocation made while this runs may be reported as overwritten.
+    pub fn check_canaries(&self) -> Result<(), usize> {
+        let mut end = self.next_free_store_spot.load(Ordering::Acquire);
+        while end != 0 {
+            let at = end.checked_sub(CANARY).ok_or(end)?;
+            let store = self.store().cast::<u8>();
+            let pattern = unsafe { store.add(at).cast::<[u8; 8]>().read_unaligned() };
+            let prev = self.canary_link(at);
+            if pattern != PATTERN || prev > at {
+                return Err(at);
+            }
+            end = prev;
+        }
+        Ok(())
+    }
+
+    /// Write a canary at `at`, after a reservation that began at cursor `prev`.
+    pub(crate) fn write_canary(&self, at: usize, prev: usize) {
+        let canary = unsafe { self.store().cast::<u8>().add(at) };
+        unsafe {
+            canary.cast::<[u8; 8]>().write_unaligned(PATTERN);
+            canary
+                .add(PATTERN.len())
+                .cast::<usize>()
+                .write_unaligned(prev);
+        }
+    }
+
+    /// The cursor the reservation before the canary at `at` began at.
+    pub(crate) fn canary_link(&self, at: usize) -> usize {
+        unsafe {
+            self.store()
+                .cast::<u8>()
+                .add(at + PATTERN.len())
+                .cast::<usize>()
+                .read_unaligned()
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Values that are dropped in a fixed order relative to a parent value.
+
+use core::ptr;
+
+use crate::{atomic::Ordering, DynArena, TypeInfo};
+
+/// Which of a parent and its children an arena drops first, set with
+/// [`DynArena::set_child_drop_order`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DropOrder {
+    /// Drop children before their parents, so that a child's `Drop` can still
+    /// use its parent, e.g. to unregister itself.
+    #[default]
+    ChildrenFirst,
+    /// Drop children after their parents, e.g. when a parent's `Drop` flushes
+    /// into its children.
+    ParentsFirst,
+}
+
+impl TypeInfo {
+    /// The info for a value of type T acquired as the child of another.
+    fn of_child<T>() -> &'static TypeInfo {
+        const {
+            &TypeInfo {
+                child: true,
+                ..*TypeInfo::of::<T>()
+            }
+        }
+    }
+}
+
+impl DynArena {
+    /// acquire a reference to a value of type T that is initialized with the given
+    /// value and dropped on the side of `parent` set by
+    /// [`DynArena::set_child_drop_order`], wherever the two are in the arena.
+    ///
+    /// Returns None if `parent` is not in this arena, since only values dropped with
+    /// it can be ordered against it.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_child_of<P: ?Sized, T>(&self, parent: &P, val: T) -> Option<&T> {
+        let store = self.store();
+        let parent = ptr::from_ref(parent).cast::<u8>().addr();
+        let start = store.cast::<u8>().addr();
+        if !(start..start + store.len()).contains(&parent) {
+            return None;
+        }
+        let mut slot = self.claim_drop_slot_for::<T>()?;
+        let (place, ptr) = self.get_ptr_place::<T>()?;
+        let val = ptr.write(val);
+        slot.fill(place, TypeInfo::of_child::<T>());
+        Some(&*val)
+    }
+
+    /// Set whether children acquired with [`DynArena::acquire_child_of`] are dropped
+    /// before their parents, the default, or after them.
+    pub fn set_child_drop_order(&self, order: DropOrder) {
+        self.parents_first
+            .store(order == DropOrder::ParentsFirst, Ordering::Relaxed);
+    }
+
+    /// The order children and their parents are dropped in.
+    pub fn child_drop_order(&self) -> DropOrder {
+        if self.parents_first.load(Ordering::Relaxed) {
+            DropOrder::ParentsFirst
+        } else {
+            DropOrder::ChildrenFirst
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Deep copies of values from one arena into another.
+
+use crate::DynArena;
+
+/// A value that can be copied into an arena along with everything it references,
+/// e.g. to keep a structure built in a scratch arena in a long lived one.
+///
+/// References are copied by acquiring a copy of their target in the destination,
+/// so the copy of a `&'a T` is a `&'b T::Cloned` that lives as long as the destination.
+/// With the `derive` feature, `#[derive(CloneIn)]` implements it field by field.
+pub trait CloneIn<'b> {
+    /// The type of the copy: Self with its references moved to the destination.
+    type Cloned: 'b;
+
+    /// Copy the value, acquiring copies of everything it references from `dst`.
+    /// Returns None if `dst` runs out of room.
+    fn clone_in(&self, dst: &'b DynArena) -> Option<Self::Cloned>;
+}
+
+macro_rules! clone_in_copy {
+    ($($ty:ty),* $(,)?) => {$(
+        impl<'b> CloneIn<'b> for $ty {
+            type Cloned = $ty;
+
+            fn clone_in(&self, _dst: &'b DynArena) -> Option<$ty> {
+                Some(*self)
+            }
+        }
+    )*};
+}
+
+clone_in_copy!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+);
+
+impl<'b, T: CloneIn<'b>> CloneIn<'b> for Option<T> {
+    type Cloned = Option<T::Cloned>;
+
+    fn clone_in(&self, dst: &'b DynArena) -> Option<Self::Cloned> {
+        match self {
+            Some(val) => Some(Some(val.clone_in(dst)?)),
+            None => Some(None),
+        }
+    }
+}
+
+impl<'b, T: CloneIn<'b>> CloneIn<'b> for &T {
+    type Cloned = &'b T::Cloned;
+
+    fn clone_in(&self, dst: &'b DynArena) -> Option<Self::Cloned> {
+        dst.acquire((**self).clone_in(dst)?)
+    }
+}
+
+impl<'b, T: CloneIn<'b>> CloneIn<'b> for &[T] {
+    type Cloned = &'b [T::Cloned];
+
+    fn clone_in(&self, dst: &'b DynArena) -> Option<Self::Cloned> {
+        dst.acquire_slice_try_fill_with(self.len(), |i| self[i].clone_in(dst))
+            .map(|slice| &*slice)
+    }
+}
+
+impl<'b> CloneIn<'b> for &str {
+    type Cloned = &'b str;
+
+    fn clone_in(&self, dst: &'b DynArena) -> Option<Self::Cloned> {
+        let bytes = dst.acquire_slice_fill_with(self.len(), |i| self.as_bytes()[i])?;
+        Some(unsafe { core::str::from_utf8_unchecked(bytes) })
+    }
+}
+
+impl DynArena {
+    /// acquire a deep copy of `val`, copying everything it references into this arena too.
+    pub fn acquire_clone<'b, T: CloneIn<'b> + ?Sized>(&'b self, val: &T) -> Option<&'b T::Cloned> {
+        self.acquire(val.clone_in(self)?)
+    }
+}
@@ -0,0 +1,238 @@
+//! Collecting iterators of unknown length into an arena.
+
+use core::{
+    iter::FusedIterator,
+    marker::PhantomData,
+    mem::{self, size_of},
+    ptr, slice,
+};
+
+use crate::{slice_header, DynArena};
+
+/// One chunk of the values collected from an iterator.
+struct Chunk<T> {
+    place: usize,
+    start: *mut T,
+    len: usize,
+    capacity: usize,
+    next: *mut Chunk<T>,
+}
+
+/// Drops every value collected so far if collecting the rest panics or runs out of room.
+struct Collecting<T> {
+    first: *mut Chunk<T>,
+}
+
+impl<T> Drop for Collecting<T> {
+    fn drop(&mut self) {
+        let mut chunk = self.first;
+        while let Some(current) = unsafe { chunk.as_ref() } {
+            unsafe { ptr::slice_from_raw_parts_mut(current.start, current.len).drop_in_place() };
+            chunk = current.next;
+        }
+    }
+}
+
+/// The values collected from an iterator by [`DynArena::acquire_chunked`],
+/// in chunks that are each contiguous.
+pub struct Chunked<'a, T> {
+    first: *const Chunk<T>,
+    len: usize,
+    _values: PhantomData<&'a [T]>,
+}
+
+unsafe impl<T: Sync> Send for Chunked<'_, T> {}
+unsafe impl<T: Sync> Sync for Chunked<'_, T> {}
+
+impl<T> Clone for Chunked<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Chunked<'_, T> {}
+
+impl<'a, T> Chunked<'a, T> {
+    /// The number of values collected.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no values were collected.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The chunks of values, in order.
+    pub fn chunks(&self) -> Chunks<'a, T> {
+        Chunks {
+            next: self.first,
+            _values: PhantomData,
+        }
+    }
+
+    /// The values, in order.
+    pub fn iter(&self) -> impl Iterator<Item = &'a T> + 'a {
+        self.chunks().flatten()
+    }
+}
+
+/// An iterator over the chunks of a [`Chunked`].
+pub struct Chunks<'a, T> {
+    next: *const Chunk<T>,
+    _values: PhantomData<&'a [T]>,
+}
+
+impl<'a, T> Iterator for Chunks<'a, T> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<&'a [T]> {
+        let chunk = unsafe { self.next.as_ref() }?;
+        self.next = chunk.next;
+        Some(unsafe { slice::from_raw_parts(chunk.start, chunk.len) })
+    }
+}
+
+impl<T> FusedIterator for Chunks<'_, T> {}
+
+impl DynArena {
+    /// Reserve a chunk for up to `want` values of type T, halving `want` until one fits.
+    /// Afterwards `want` is doubled for the next chunk.
+    #[cfg_attr(feature = "record", track_caller)]
+    fn reserve_chunk<T>(&self, want: &mut usize) -> Option<*mut Chunk<T>> {
+        let (_, record) = self.get_ptr_place::<Chunk<T>>()?;
+        let (place, start) = loop {
+            match self.reserve_slice::<T>(*want) {
+                Some(reserved) => break reserved,
+                None if *want > 1 => *want /= 2,
+                None => return None,
+            }
+        };
+        let chunk = record.write(Chunk {
+            place,
+            start,
+            len: 0,
+            capacity: *want,
+            next: ptr::null_mut(),
+        });
+        *want = want.saturating_mul(2);
+        Some(chunk)
+    }
+
+    /// Move every value out of `iter` into chunks that are not yet in the drop queue.
+    /// Returns the guard over the chunks and the number of values.
+    #[cfg_attr(feature = "record", track_caller)]
+    fn collect_chunks<T>(
+        &self,
+        iter: impl IntoIterator<Item = T>,
+    ) -> Option<(Collecting<T>, usize)> {
+        let iter = iter.into_iter();
+        let mut want = if size_of::<T>() == 0 {
+            usize::MAX
+        } else {
+            iter.size_hint().0.max(4)
+        };
+
+        let mut collecting = Collecting {
+            first: ptr::null_mut(),
+        };
+        let mut last: *mut Chunk<T> = ptr::null_mut();
+        let mut len = 0;
+        for val in iter {
+            let full = unsafe { last.as_ref() }.is_none_or(|chunk| chunk.len == chunk.capacity);
+            if full {
+                let chunk = self.reserve_chunk::<T>(&mut want)?;
+                match unsafe { last.as_mut() } {
+                    Some(last) => last.next = chunk,
+                    None => collecting.first = chunk,
+                }
+                last = chunk;
+            }
+            let chunk = unsafe { &mut *last };
+            unsafe { chunk.start.add(chunk.len).write(val) };
+            chunk.len += 1;
+            len += 1;
+        }
+        if let Some(last) = unsafe { last.as_mut() } {
+            self.trim_chunk(last);
+        }
+        Some((collecting, len))
+    }
+
+    /// Hand back the unused end of the last chunk, unless something was acquired after it.
+    fn trim_chunk<T>(&self, chunk: &mut Chunk<T>) {
+        let header = if mem::needs_drop::<T>() {
+            slice_header::<T>()
+        } else {
+            0
+        };
+        self.shrink(
+            chunk.place,
+            header + chunk.capacity * size_of::<T>(),
+            header + chunk.len * size_of::<T>(),
+        );
+        chunk.capacity = chunk.len;
+    }
+
+    /// acquire the values of an iterator whose length is not known up front,
+    /// in chunks that grow as the iterator goes on, without reserving room for
+    /// the worst case. If the arena runs out of room, the values are dropped.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_chunked<T>(&self, iter: impl IntoIterator<Item = T>) -> Option<Chunked<'_, T>> {
+        let (collecting, len) = self.collect_chunks(iter)?;
+        let first = collecting.first;
+        let mut chunks = 0;
+        let mut chunk = first;
+        while let Some(current) = unsafe { chunk.as_ref() } {
+            chunks += 1;
+            chunk = current.next;
+        }
+        // Claimed before the chunks are let go, which drops their values if the
+        // drop queue is full.
+        let mut slots = self.claim_slice_drop_slots::<T>(chunks)?;
+        mem::forget(collecting);
+        let mut chunk = first;
+        while let Some(current) = unsafe { chunk.as_ref() } {
+            self.add_slice_to_drop_queue::<T>(&mut slots, current.place, current.len);
+            chunk = current.next;
+        }
+
+        Some(Chunked {
+            first,
+            len,
+            _values: PhantomData,
+        })
+    }
+
+    /// acquire the values of an iterator whose length is not known up front as one slice.
+    /// They are collected in chunks, which are moved into a single slice if there is
+    /// more than one, leaving the room of the chunks unused until the arena is reset.
+    /// If the arena runs out of room, the values are dropped.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_from_iter<T>(&self, iter: impl IntoIterator<Item = T>) -> Option<&[T]> {
+        let (collecting, len) = self.collect_chunks(iter)?;
+        let Some(first) = (unsafe { collecting.first.as_ref() }) else {
+            return Some(&[]);
+        };
+
+        let mut slot = self.claim_slice_drop_slots::<T>(1)?;
+        if first.next.is_null() {
+            mem::forget(collecting);
+            self.add_slice_to_drop_queue::<T>(&mut slot, first.place, len);
+            return Some(unsafe { slice::from_raw_parts(first.start, len) });
+        }
+
+        let (place, start) = self.reserve_slice::<T>(len)?;
+        let mut copied = 0;
+        let mut chunk: *const Chunk<T> = first;
+        while let Some(current) = unsafe { chunk.as_ref() } {
+            unsafe { ptr::copy_nonoverlapping(current.start, start.add(copied), current.len) };
+            copied += current.len;
+            chunk = current.next;
+        }
+        mem::forget(collecting);
+        self.add_slice_to_drop_queue::<T>(&mut slot, place, len);
+
+        Some(unsafe { slice::from_raw_parts(start, len) })
+    }
+}
@@ -0,0 +1,108 @@
+//! Arenas filled during const evaluation, for tables built at compile time.
+
+use core::{mem::MaybeUninit, slice};
+
+use crate::Handle;
+
+/// An arena of up to N values of type T whose methods are all `const fn`, so that
+/// a lookup table can be acquired value by value in a `const` or `static`
+/// initializer and placed in flash, with the handle API of an [`ArenaMut`](crate::ArenaMut).
+///
+/// ```
+/// use arena_alloc::ConstArena;
+///
+/// static SQUARES: ConstArena<u32, 16> = {
+///     let mut arena = ConstArena::new();
+///     let mut i = 0;
+///     while i < 16 {
+///         arena.acquire(i * i);
+///         i += 1;
+///     }
+///     arena
+/// };
+///
+/// assert_eq!(SQUARES.as_slice()[5], 25);
+/// ```
+///
+/// Values must be `Copy`, as const evaluation can not run destructors.
+pub struct ConstArena<T, const N: usize> {
+    values: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T: Copy, const N: usize> Default for ConstArena<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy, const N: usize> ConstArena<T, N> {
+    /// Create a new, empty arena.
+    #[must_use]
+    pub const fn new() -> Self {
+        ConstArena {
+            values: [const { MaybeUninit::uninit() }; N],
+            len: 0,
+        }
+    }
+
+    /// The number of values acquired so far.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no values have been acquired.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// acquire a handle to a value of type T that is initialized with the given value.
+    pub const fn acquire(&mut self, val: T) -> Option<Handle<T>> {
+        if self.len == N {
+            return None;
+        }
+        self.values[self.len] = MaybeUninit::new(val);
+        self.len += 1;
+        Some(Handle::new(self.len - 1))
+    }
+
+    /// acquire handles to copies of every value of `vals`, returning the handle to the first.
+    /// Acquires nothing if they do not all fit.
+    pub const fn acquire_slice(&mut self, vals: &[T]) -> Option<Handle<T>> {
+        if N - self.len < vals.len() {
+            return None;
+        }
+        let first = self.len;
+        let mut i = 0;
+        while i < vals.len() {
+            self.values[self.len] = MaybeUninit::new(vals[i]);
+            self.len += 1;
+            i += 1;
+        }
+        Some(Handle::new(first))
+    }
+
+    /// Get a reference to the value a handle refers to.
+    /// Returns None if the handle did not come from this arena.
+    pub const fn get(&self, handle: Handle<T>) -> Option<&T> {
+        if handle.index() >= self.len {
+            return None;
+        }
+        Some(unsafe { self.values[handle.index()].assume_init_ref() })
+    }
+
+    /// Get the `len` values acquired from the one a handle refers to on.
+    /// Returns None if they were not all acquired from this arena.
+    pub const fn get_slice(&self, handle: Handle<T>, len: usize) -> Option<&[T]> {
+        if handle.index() > self.len || self.len - handle.index() < len {
+            return None;
+        }
+        let values = self.as_slice();
+        Some(unsafe { slice::from_raw_parts(values.as_ptr().add(handle.index()), len) })
+    }
+
+    /// Every value acquired, in order.
+    pub const fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.values.as_ptr().cast(), self.len) }
+    }
+}
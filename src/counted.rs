@@ -0,0 +1,232 @@
+//! An arena that counts its live references so it can be reset through a shared borrow.
+
+use core::{fmt, ops::Deref, ptr::NonNull};
+
+use crate::{
+    atomic::{AtomicUsize, Ordering},
+    Arena, Init,
+};
+
+/// The live count while a reset is running; acquisitions fail until it is done.
+const RESETTING: usize = usize::MAX;
+
+/// An arena whose acquisitions return [`CountedRef`] guards.
+///
+/// The arena tracks how many guards are live, which lets [`CountedArena::try_reset`]
+/// reset it through `&self` once none remain, e.g. for a `static` arena.
+pub struct CountedArena<const SIZE: usize> {
+    arena: Arena<SIZE>,
+    live: AtomicUsize,
+}
+
+/// A reference to a value in a [`CountedArena`] that keeps the arena from being reset.
+pub struct CountedRef<'a, T: ?Sized> {
+    value: &'a T,
+    live: &'a AtomicUsize,
+    generation: &'a AtomicUsize,
+}
+
+/// A pointer to a value in a [`CountedArena`] stamped with the arena's generation.
+///
+/// Unlike a [`CountedRef`] it does not keep the arena from being reset; instead
+/// [`Stamped::get`] detects that a reset has happened since it was stamped.
+pub struct Stamped<'a, T: ?Sized> {
+    value: NonNull<T>,
+    stamp: usize,
+    live: &'a AtomicUsize,
+    generation: &'a AtomicUsize,
+}
+
+unsafe impl<T: ?Sized + Sync> Send for Stamped<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for Stamped<'_, T> {}
+
+impl<T: ?Sized> Clone for Stamped<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for Stamped<'_, T> {}
+
+impl<'a, T: ?Sized> Stamped<'a, T> {
+    /// The generation of the arena when the value was stamped.
+    pub fn stamp(&self) -> usize {
+        self.stamp
+    }
+
+    /// Whether the arena has not been reset since the value was stamped.
+    /// Never true once the generation has stopped counting at `usize::MAX`.
+    pub fn is_current(&self) -> bool {
+        self.stamp != usize::MAX && self.generation.load(Ordering::Acquire) == self.stamp
+    }
+
+    /// Get a counted reference to the value if the arena has not been reset since it was stamped.
+    pub fn get(&self) -> Option<CountedRef<'a, T>> {
+        enter(self.live)?;
+        // Gives the count back if the value turns out to be stale.
+        let guard = CountedRef {
+            value: &(),
+            live: self.live,
+            generation: self.generation,
+        };
+        if !self.is_current() {
+            return None;
+        }
+        core::mem::forget(guard);
+        Some(CountedRef {
+            value: unsafe { self.value.as_ref() },
+            live: self.live,
+            generation: self.generation,
+        })
+    }
+}
+
+/// Count a new live reference, unless a reset is running.
+fn enter(live: &AtomicUsize) -> Option<()> {
+    live.fetch_update(Ordering::Acquire, Ordering::Relaxed, |n| {
+        (n != RESETTING).then_some(n + 1)
+    })
+    .ok()
+    .map(drop)
+}
+
+impl<T: ?Sized> Deref for CountedRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T: ?Sized> Clone for CountedRef<'_, T> {
+    fn clone(&self) -> Self {
+        self.live.fetch_add(1, Ordering::Relaxed);
+        CountedRef {
+            value: self.value,
+            live: self.live,
+            generation: self.generation,
+        }
+    }
+}
+
+impl<'a, T: ?Sized> CountedRef<'a, T> {
+    /// Stamp the value with the arena's current generation, giving a pointer to it
+    /// that does not keep the arena from being reset.
+    pub fn stamp(this: &Self) -> Stamped<'a, T> {
+        Stamped {
+            value: NonNull::from(this.value),
+            stamp: this.generation.load(Ordering::Acquire),
+            live: this.live,
+            generation: this.generation,
+        }
+    }
+}
+
+impl<T: ?Sized> Drop for CountedRef<'_, T> {
+    fn drop(&mut self) {
+        self.live.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for CountedRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<const SIZE: usize> Default for CountedArena<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize> CountedArena<SIZE> {
+    /// Create a new counted arena with a fixed size buffer of SIZE bytes.
+    #[must_use]
+    pub const fn new() -> Self {
+        CountedArena {
+            arena: Arena::new(),
+            live: AtomicUsize::new(0),
+        }
+    }
+
+    /// Run an acquisition while counted as a live reference.
+    /// Fails if the arena is being reset.
+    fn counted<'a, T: ?Sized>(
+        &'a self,
+        acquire: impl FnOnce(&'a Arena<SIZE>) -> Option<&'a T>,
+    ) -> Option<CountedRef<'a, T>> {
+        enter(&self.live)?;
+        // Gives the count back if the acquisition fails or panics.
+        let guard = CountedRef {
+            value: &(),
+            live: &self.live,
+            generation: &self.arena.generation,
+        };
+        let value = acquire(&self.arena)?;
+        core::mem::forget(guard);
+        Some(CountedRef {
+            value,
+            live: &self.live,
+            generation: &self.arena.generation,
+        })
+    }
+
+    /// acquire a counted reference to a value of type T that can be initialized with
+    /// the Init trait, using the default value of the InitArg.
+    pub fn acquire_init_default<T: Init>(&self) -> Option<CountedRef<'_, T>>
+    where
+        T::InitArg: Default,
+    {
+        self.counted(|arena| arena.acquire_init_default())
+    }
+
+    /// acquire a counted reference to a value of type T that can be initialized with
+    /// the Init trait, using a given InitArg.
+    pub fn acquire_init<T: Init>(&self, arg: T::InitArg) -> Option<CountedRef<'_, T>> {
+        self.counted(|arena| arena.acquire_init(arg))
+    }
+
+    /// acquire a counted reference to a value of type T that is initialized with it's default value.
+    pub fn acquire_default<T: Default>(&self) -> Option<CountedRef<'_, T>> {
+        self.counted(|arena| arena.acquire_default())
+    }
+
+    /// acquire a counted reference to a value of type T that is initialized with the given value.
+    pub fn acquire<T>(&self, val: T) -> Option<CountedRef<'_, T>> {
+        self.counted(|arena| arena.acquire(val))
+    }
+
+    /// The number of times the arena has been reset.
+    pub fn generation(&self) -> usize {
+        self.arena.generation()
+    }
+
+    /// The number of live counted references.
+    pub fn live(&self) -> usize {
+        match self.live.load(Ordering::Acquire) {
+            RESETTING => 0,
+            n => n,
+        }
+    }
+
+    /// Reset the arena if no counted references are live.
+    /// Returns whether the reset happened.
+    pub fn try_reset(&self) -> bool {
+        if self
+            .live
+            .compare_exchange(0, RESETTING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        unsafe { self.arena.reset_unchecked() };
+        self.live.store(0, Ordering::Release);
+        true
+    }
+
+    /// Drop every value in the arena and make its whole capacity available again.
+    pub fn reset(&mut self) {
+        self.arena.reset();
+    }
+}
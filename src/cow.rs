@@ -0,0 +1,99 @@
+//! Data that is borrowed until it first needs to change, and then lives in an arena.
+
+use core::{fmt, ops::Deref};
+
+use crate::DynArena;
+
+/// A value that can be copied into an arena as a mutable value.
+pub trait ToOwnedIn {
+    /// acquire a mutable copy of the value from `arena`.
+    // Each copy is a fresh acquisition, so it is not aliased through the arena.
+    #[allow(clippy::mut_from_ref)]
+    fn to_owned_in<'a>(&self, arena: &'a DynArena) -> Option<&'a mut Self>;
+}
+
+impl<T: Clone> ToOwnedIn for T {
+    fn to_owned_in<'a>(&self, arena: &'a DynArena) -> Option<&'a mut Self> {
+        arena.try_acquire_mut(self.clone()).ok()
+    }
+}
+
+impl<T: Clone> ToOwnedIn for [T] {
+    fn to_owned_in<'a>(&self, arena: &'a DynArena) -> Option<&'a mut Self> {
+        arena.acquire_slice_try_fill_with(self.len(), |i| Some(self[i].clone()))
+    }
+}
+
+impl ToOwnedIn for str {
+    fn to_owned_in<'a>(&self, arena: &'a DynArena) -> Option<&'a mut Self> {
+        let bytes = self.as_bytes().to_owned_in(arena)?;
+        Some(unsafe { core::str::from_utf8_unchecked_mut(bytes) })
+    }
+}
+
+/// A value that is either borrowed or a mutable copy in an arena, e.g. a slice
+/// of parser input that is only copied if it has to be changed.
+pub enum ArenaCow<'a, T: ?Sized> {
+    /// Borrowed data that has not been changed.
+    Borrowed(&'a T),
+    /// A copy of the data in an arena.
+    Owned(&'a mut T),
+}
+
+impl<'a, T: ?Sized> ArenaCow<'a, T> {
+    /// Whether the data is still borrowed.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self, ArenaCow::Borrowed(_))
+    }
+
+    /// Whether the data has been copied into an arena.
+    pub fn is_owned(&self) -> bool {
+        matches!(self, ArenaCow::Owned(_))
+    }
+
+    /// Get a mutable reference to the data, copying it into `arena` first if it is borrowed.
+    /// Returns None if the arena has no room for the copy.
+    pub fn to_mut(&mut self, arena: &'a DynArena) -> Option<&mut T>
+    where
+        T: ToOwnedIn,
+    {
+        if let ArenaCow::Borrowed(borrowed) = *self {
+            *self = ArenaCow::Owned(borrowed.to_owned_in(arena)?);
+        }
+        match self {
+            ArenaCow::Owned(owned) => Some(owned),
+            ArenaCow::Borrowed(_) => unreachable!(),
+        }
+    }
+
+    /// Get the data for as long as it lives.
+    pub fn into_ref(self) -> &'a T {
+        match self {
+            ArenaCow::Borrowed(borrowed) => borrowed,
+            ArenaCow::Owned(owned) => owned,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for ArenaCow<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            ArenaCow::Borrowed(borrowed) => borrowed,
+            ArenaCow::Owned(owned) => owned,
+        }
+    }
+}
+
+impl<'a, T: ?Sized> From<&'a T> for ArenaCow<'a, T> {
+    fn from(borrowed: &'a T) -> Self {
+        ArenaCow::Borrowed(borrowed)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ArenaCow<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
@@ -0,0 +1,44 @@
+//! A pool at the end of an arena kept for critical acquisitions.
+
+use core::mem::{align_of, size_of};
+
+use crate::{atomic::Ordering, DynArena, TypeInfo};
+
+impl DynArena {
+    /// Keep the last `bytes` bytes of the arena for [`DynArena::acquire_critical`],
+    /// so that best-effort acquisitions, e.g. log records, fail once they reach
+    /// them and can never starve safety-critical ones sharing the arena. Zero
+    /// gives the whole arena to every acquisition again.
+    ///
+    /// Only the backing store is pooled; critical values that need dropping still
+    /// take slots from the drop queue shared with the rest.
+    pub fn set_critical_reserve(&self, bytes: usize) {
+        self.critical.store(bytes, Ordering::Relaxed);
+    }
+
+    /// The number of bytes kept for critical acquisitions.
+    pub fn critical_reserve(&self) -> usize {
+        self.critical.load(Ordering::Relaxed)
+    }
+
+    /// acquire a reference to a value of type T that is initialized with the given value,
+    /// from anywhere in the arena including its critical pool.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_critical<T>(&self, val: T) -> Option<&T> {
+        let mut slot = self.claim_drop_slot_for::<T>()?;
+        let place =
+            self.reserve_within(size_of::<T>(), align_of::<T>(), self.capacity(), usize::MAX);
+        #[cfg(feature = "record")]
+        self.record::<T>(place.is_some());
+        let place = place?;
+        let ptr = unsafe { self.store().cast::<u8>().add(place).cast::<T>() };
+        unsafe { ptr.write(val) };
+        slot.fill(place, TypeInfo::of::<T>());
+        Some(unsafe { &*ptr })
+    }
+
+    /// The size of the part of the store that acquisitions not flagged critical may use.
+    pub(crate) fn best_effort_capacity(&self) -> usize {
+        self.capacity().saturating_sub(self.critical_reserve())
+    }
+}
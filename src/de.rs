@@ -0,0 +1,248 @@
+//! Deserializing with serde into an arena, for formats such as postcard on no_std
+//! without `alloc`.
+
+use core::{fmt, marker::PhantomData};
+
+use serde::de::{self, DeserializeSeed, Deserializer, Error as _, SeqAccess, Visitor};
+
+use crate::DynArena;
+
+/// A value that can be deserialized with serde, acquiring what it owns from an
+/// arena: strings and sequences become `&'a str` and `&'a [T]` in the arena rather
+/// than `String` and `Vec<T>`, so they need not borrow from the input.
+///
+/// Implement it for structs of your own by deserializing them as tuples or maps
+/// and their fields with [`DynArena::seed`].
+pub trait DeserializeIn<'a, 'de>: Sized {
+    /// Deserialize a value from `deserializer`, acquiring what it owns from `arena`.
+    fn deserialize_in<D: Deserializer<'de>>(
+        arena: &'a DynArena,
+        deserializer: D,
+    ) -> Result<Self, D::Error>;
+}
+
+/// A `DeserializeSeed` that deserializes a T into an arena, made with [`DynArena::seed`].
+pub struct ArenaSeed<'a, T> {
+    arena: &'a DynArena,
+    _val: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for ArenaSeed<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ArenaSeed<'_, T> {}
+
+impl<'a, 'de, T: DeserializeIn<'a, 'de>> DeserializeSeed<'de> for ArenaSeed<'a, T> {
+    type Value = T;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T, D::Error> {
+        T::deserialize_in(self.arena, deserializer)
+    }
+}
+
+impl DynArena {
+    /// A seed that deserializes a T, acquiring what it owns from the arena, e.g.
+    /// for `SeqAccess::next_element_seed` or postcard's `Deserializer`.
+    ///
+    /// ```
+    /// use arena_alloc::Arena;
+    /// use serde::de::DeserializeSeed;
+    ///
+    /// static ARENA: Arena<256> = Arena::new();
+    ///
+    /// let input = [2, 3, b'a', b'd', b'c', 2, b'i', b'f'];
+    /// let mut de = postcard::Deserializer::from_bytes(&input);
+    /// let names: &[&str] = ARENA.seed().deserialize(&mut de).unwrap();
+    /// assert_eq!(names, ["adc", "if"]);
+    /// ```
+    pub fn seed<T>(&self) -> ArenaSeed<'_, T> {
+        ArenaSeed {
+            arena: self,
+            _val: PhantomData,
+        }
+    }
+}
+
+/// The error to fail with when the arena has no room for what is deserialized.
+fn out_of_space<E: de::Error>() -> E {
+    E::custom("arena is out of space")
+}
+
+macro_rules! deserialize_in_owned {
+    ($($ty:ty),* $(,)?) => {$(
+        impl<'a, 'de> DeserializeIn<'a, 'de> for $ty {
+            fn deserialize_in<D: Deserializer<'de>>(
+                _arena: &'a DynArena,
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                de::Deserialize::deserialize(deserializer)
+            }
+        }
+    )*};
+}
+
+deserialize_in_owned!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+);
+
+impl<'a, 'de> DeserializeIn<'a, 'de> for &'a str {
+    fn deserialize_in<D: Deserializer<'de>>(
+        arena: &'a DynArena,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        struct StrVisitor<'a>(&'a DynArena);
+
+        impl<'a> Visitor<'_> for StrVisitor<'a> {
+            type Value = &'a str;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a string")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<&'a str, E> {
+                self.0.concat_strs(&[v]).ok_or_else(out_of_space)
+            }
+        }
+
+        deserializer.deserialize_str(StrVisitor(arena))
+    }
+}
+
+impl<'a, 'de, T: DeserializeIn<'a, 'de> + 'a> DeserializeIn<'a, 'de> for &'a [T] {
+    fn deserialize_in<D: Deserializer<'de>>(
+        arena: &'a DynArena,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        struct SeqVisitor<'a, T>(&'a DynArena, PhantomData<fn() -> T>);
+
+        impl<'a, 'de, T: DeserializeIn<'a, 'de> + 'a> Visitor<'de> for SeqVisitor<'a, T> {
+            type Value = &'a [T];
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a sequence")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<&'a [T], A::Error> {
+                let mut error = None;
+                let elements =
+                    core::iter::from_fn(|| match seq.next_element_seed(self.0.seed::<T>()) {
+                        Ok(val) => val,
+                        Err(e) => {
+                            error = Some(e);
+                            None
+                        }
+                    });
+                let slice = self.0.acquire_from_iter(elements);
+                match (error, slice) {
+                    (Some(e), _) => Err(e),
+                    (None, Some(slice)) => Ok(slice),
+                    (None, None) => Err(out_of_space()),
+                }
+            }
+        }
+
+        deserializer.deserialize_seq(SeqVisitor(arena, PhantomData))
+    }
+}
+
+impl<'a, 'de, T: DeserializeIn<'a, 'de> + 'a> DeserializeIn<'a, 'de> for &'a T {
+    fn deserialize_in<D: Deserializer<'de>>(
+        arena: &'a DynArena,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let val = T::deserialize_in(arena, deserializer)?;
+        arena.acquire(val).ok_or_else(out_of_space)
+    }
+}
+
+impl<'a, 'de, T: DeserializeIn<'a, 'de>> DeserializeIn<'a, 'de> for Option<T> {
+    fn deserialize_in<D: Deserializer<'de>>(
+        arena: &'a DynArena,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        struct OptionVisitor<'a, T>(&'a DynArena, PhantomData<fn() -> T>);
+
+        impl<'a, 'de, T: DeserializeIn<'a, 'de>> Visitor<'de> for OptionVisitor<'a, T> {
+            type Value = Option<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an option")
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Option<T>, E> {
+                Ok(None)
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Option<T>, E> {
+                Ok(None)
+            }
+
+            fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Option<T>, D::Error> {
+                T::deserialize_in(self.0, d).map(Some)
+            }
+        }
+
+        deserializer.deserialize_option(OptionVisitor(arena, PhantomData))
+    }
+}
+
+macro_rules! deserialize_in_tuple {
+    ($len:literal => $($name:ident)+) => {
+        impl<'a, 'de, $($name: DeserializeIn<'a, 'de>),+> DeserializeIn<'a, 'de> for ($($name,)+) {
+            fn deserialize_in<D: Deserializer<'de>>(
+                arena: &'a DynArena,
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                struct TupleVisitor<'a, T>(&'a DynArena, PhantomData<fn() -> T>);
+
+                impl<'a, 'de, $($name: DeserializeIn<'a, 'de>),+> Visitor<'de>
+                    for TupleVisitor<'a, ($($name,)+)>
+                {
+                    type Value = ($($name,)+);
+
+                    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        write!(f, "a tuple of {} values", $len)
+                    }
+
+                    #[allow(non_snake_case, unused_assignments)]
+                    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                        let mut index = 0;
+                        $(
+                            let $name = seq
+                                .next_element_seed(self.0.seed::<$name>())?
+                                .ok_or_else(|| A::Error::invalid_length(index, &self))?;
+                            index += 1;
+                        )+
+                        Ok(($($name,)+))
+                    }
+                }
+
+                deserializer.deserialize_tuple($len, TupleVisitor(arena, PhantomData))
+            }
+        }
+    };
+}
+
+deserialize_in_tuple!(1 => T0);
+deserialize_in_tuple!(2 => T0 T1);
+deserialize_in_tuple!(3 => T0 T1 T2);
+deserialize_in_tuple!(4 => T0 T1 T2 T3);
@@ -0,0 +1,177 @@
+//! Interning of values, so that equal values share one place in an arena.
+
+use core::hash::{Hash, Hasher};
+
+use crate::DynArena;
+
+/// A 64-bit FNV-1a hasher, as core has no hasher to build tables with.
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// The hash of `val`, for indexing a table of `len` buckets.
+fn bucket<T: Hash + ?Sized>(val: &T, len: usize) -> usize {
+    let mut hasher = Fnv::default();
+    val.hash(&mut hasher);
+    (hasher.finish() % len as u64) as usize
+}
+
+/// Up to N distinct values of type T kept in an arena, where acquiring a value
+/// equal to one already there returns a reference to the existing one.
+///
+/// This hash-conses the nodes of an AST or the descriptors of a type system,
+/// so that duplicates take no space and equal nodes can be compared by address.
+pub struct DedupArena<'a, T, const N: usize> {
+    arena: &'a DynArena,
+    buckets: [Option<&'a T>; N],
+    len: usize,
+}
+
+impl<'a, T: Hash + Eq, const N: usize> DedupArena<'a, T, N> {
+    /// Create a new table with room for N distinct values kept in `arena`.
+    #[must_use]
+    pub const fn new(arena: &'a DynArena) -> Self {
+        DedupArena {
+            arena,
+            buckets: [None; N],
+            len: 0,
+        }
+    }
+
+    /// The number of distinct values acquired.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no values have been acquired.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The bucket holding a value equal to `val`, or else the empty bucket it would go in.
+    fn find(&self, val: &T) -> Option<usize> {
+        let start = bucket(val, N.max(1));
+        (0..N)
+            .map(|i| (start + i) % N)
+            .find(|&i| self.buckets[i].is_none_or(|existing| existing == val))
+    }
+
+    /// The value equal to `val` that has already been acquired, if any.
+    pub fn get(&self, val: &T) -> Option<&'a T> {
+        self.buckets[self.find(val)?]
+    }
+
+    /// acquire a reference to a value equal to `val`, reusing the one already
+    /// acquired if there is one. Gives `val` back if it is new and the table is
+    /// full or the arena is out of space.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn intern(&mut self, val: T) -> Result<&'a T, T> {
+        let Some(i) = self.find(&val) else {
+            return Err(val);
+        };
+        if let Some(existing) = self.buckets[i] {
+            return Ok(existing);
+        }
+        let val = self.arena.try_acquire(val)?;
+        self.buckets[i] = Some(val);
+        self.len += 1;
+        Ok(val)
+    }
+}
+
+/// How well a [`StrTable`] has deduplicated the strings given to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InternStats {
+    /// The number of strings that were already in the table.
+    pub hits: usize,
+    /// The number of strings that had to be acquired.
+    pub misses: usize,
+    /// The number of bytes the hits would have taken, to weigh against the size of the table.
+    pub saved: usize,
+}
+
+/// A table of up to N distinct strings kept in an arena, so that strings that
+/// come up again and again (metric names, JSON keys) are only stored once.
+pub struct StrTable<'a, const N: usize> {
+    arena: &'a DynArena,
+    buckets: [Option<&'a str>; N],
+    len: usize,
+    stats: InternStats,
+}
+
+impl<'a, const N: usize> StrTable<'a, N> {
+    /// Create a new table with room for N distinct strings kept in `arena`.
+    #[must_use]
+    pub const fn new(arena: &'a DynArena) -> Self {
+        StrTable {
+            arena,
+            buckets: [None; N],
+            len: 0,
+            stats: InternStats {
+                hits: 0,
+                misses: 0,
+                saved: 0,
+            },
+        }
+    }
+
+    /// The number of distinct strings in the table.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no strings have been interned.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The hits and misses of the table so far.
+    pub fn stats(&self) -> InternStats {
+        self.stats
+    }
+
+    /// The bucket holding `s`, or else the empty bucket it would go in.
+    fn find(&self, s: &str) -> Option<usize> {
+        let start = bucket(s, N.max(1));
+        (0..N)
+            .map(|i| (start + i) % N)
+            .find(|&i| self.buckets[i].is_none_or(|existing| existing == s))
+    }
+
+    /// acquire a copy of `s`, reusing the copy already in the table if there is one.
+    /// Once the table is full, new strings are still acquired but not added to it.
+    /// Returns None if the arena is out of space.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn intern_str(&mut self, s: &str) -> Option<&'a str> {
+        let i = self.find(s);
+        if let Some(existing) = i.and_then(|i| self.buckets[i]) {
+            self.stats.hits += 1;
+            self.stats.saved += s.len();
+            return Some(existing);
+        }
+        let copy = self.arena.concat_strs(&[s])?;
+        self.stats.misses += 1;
+        if let Some(i) = i {
+            self.buckets[i] = Some(copy);
+            self.len += 1;
+        }
+        Some(copy)
+    }
+}
@@ -0,0 +1,108 @@
+//! What an arena does when a destructor panics while it drops its values.
+
+#[cfg(feature = "std")]
+use core::{any::Any, cell::Cell};
+
+#[cfg(feature = "std")]
+use std::{boxed::Box, panic};
+
+use crate::{atomic::Ordering, DynArena};
+
+/// What an arena does when the destructor of one of its values, or one of its
+/// finalizers, panics while it is reset or dropped, set with
+/// [`DynArena::set_drop_panic`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DropPanic {
+    /// Drop the rest of the values, then carry on with the panic, so that one
+    /// failing destructor does not leak the resources of all those after it.
+    ///
+    /// With `std` every panic is caught and the first is resumed once all values
+    /// are dropped. Without it the rest are dropped as the panic unwinds, so a
+    /// second panic aborts.
+    #[default]
+    Continue,
+    /// Abort, for code that cannot trust the state a failing destructor leaves.
+    /// Without `std` this is done by panicking again while unwinding.
+    Abort,
+}
+
+impl DynArena {
+    /// Set what the arena does when a destructor panics while it drops its values.
+    pub fn set_drop_panic(&self, policy: DropPanic) {
+        self.abort_on_drop_panic
+            .store(policy == DropPanic::Abort, Ordering::Relaxed);
+    }
+
+    /// What the arena does when a destructor panics while it drops its values.
+    pub fn drop_panic(&self) -> DropPanic {
+        if self.abort_on_drop_panic.load(Ordering::Relaxed) {
+            DropPanic::Abort
+        } else {
+            DropPanic::Continue
+        }
+    }
+}
+
+/// A rewind of an arena in progress, which finishes it if a destructor unwinds
+/// out of it.
+pub(crate) struct DropPass<'a> {
+    arena: &'a DynArena,
+    store: usize,
+    drops: usize,
+    padding: usize,
+    /// The first panic caught from a destructor.
+    #[cfg(feature = "std")]
+    panic: Cell<Option<Box<dyn Any + Send>>>,
+}
+
+impl<'a> DropPass<'a> {
+    /// Start rewinding `arena` to the given store offset, drop queue entry and padding.
+    pub(crate) fn start(arena: &'a DynArena, store: usize, drops: usize, padding: usize) -> Self {
+        DropPass {
+            arena,
+            store,
+            drops,
+            padding,
+            #[cfg(feature = "std")]
+            panic: Cell::new(None),
+        }
+    }
+
+    /// Call a destructor or finalizer.
+    pub(crate) fn call(&self, f: impl FnOnce()) {
+        #[cfg(feature = "std")]
+        if let Err(payload) = panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+            if self.arena.drop_panic() == DropPanic::Abort {
+                std::process::abort();
+            }
+            let first = self.panic.take().unwrap_or(payload);
+            self.panic.set(Some(first));
+        }
+        #[cfg(not(feature = "std"))]
+        f();
+    }
+
+    /// End the rewind, carrying on with the first panic caught from a destructor.
+    pub(crate) fn finish(self) {
+        #[cfg(feature = "std")]
+        let panic = self.panic.take();
+        core::mem::forget(self);
+        #[cfg(feature = "std")]
+        if let Some(panic) = panic {
+            panic::resume_unwind(panic);
+        }
+    }
+}
+
+impl Drop for DropPass<'_> {
+    fn drop(&mut self) {
+        // Only reached when a destructor unwinds out of `call`.
+        assert!(
+            self.arena.drop_panic() == DropPanic::Continue,
+            "arena destructor panicked"
+        );
+        // Every finalizer and destructor that has run is gone from the arena, so
+        // rewinding again runs the rest.
+        unsafe { self.arena.rewind_to(self.store, self.drops, self.padding) };
+    }
+}
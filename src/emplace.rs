@@ -0,0 +1,112 @@
+//! Construction of structs field by field in the arena.
+
+use core::mem::{align_of, size_of};
+
+use crate::{DropClaim, DynArena, TypeInfo};
+
+/// acquire a struct from an arena, writing each field straight into its place in
+/// the arena so that no temporary of the whole struct is ever built on the stack,
+/// e.g. for a struct holding a buffer of several kilobytes.
+///
+/// Evaluates to `Option<&T>`, which is None if the arena has no room, in which case
+/// no field is evaluated. Every field must be given; the `..base` syntax and structs
+/// with generic parameters are not supported. If evaluating a field panics, the
+/// fields already written are leaked.
+///
+/// ```
+/// use arena_alloc::{emplace, Arena};
+///
+/// struct Frame {
+///     len: usize,
+///     data: [u8; 4096],
+/// }
+///
+/// static ARENA: Arena<8192> = Arena::new();
+///
+/// let frame = emplace!(ARENA, Frame { len: 0, data: [0; 4096] }).unwrap();
+/// assert_eq!(frame.data.len(), 4096);
+/// ```
+#[macro_export]
+macro_rules! emplace {
+    ($arena:expr, $($ty:ident)::+ { $($field:ident : $val:expr),* $(,)? }) => {{
+        let arena: &$crate::DynArena = &$arena;
+        match arena.reserve_emplace::<$($ty)::+>() {
+            ::core::option::Option::Some((place, ptr)) => {
+                // Names every field without running anything, so that a missing field
+                // is a compile error rather than an uninitialized one.
+                #[allow(unreachable_code, clippy::diverging_sub_expression)]
+                if false {
+                    let _ = $($ty)::+ { $($field: ::core::unreachable!()),* };
+                }
+                $(unsafe { $crate::DynArena::emplace_field(::core::ptr::addr_of_mut!((*ptr).$field)) }
+                    .write($val);)*
+                ::core::option::Option::Some(unsafe { arena.finish_emplace::<$($ty)::+>(place) })
+            }
+            ::core::option::Option::None => ::core::option::Option::None,
+        }
+    }};
+}
+
+/// The place of a value being written by [`emplace!`], and the drop queue entry
+/// claimed for it.
+#[doc(hidden)]
+pub struct EmplacePlace<'a> {
+    place: usize,
+    slot: DropClaim<'a>,
+}
+
+/// A field of a value being written by [`emplace!`], which is never referenced
+/// until it is written, as it may be unaligned in a packed struct.
+#[doc(hidden)]
+pub struct EmplaceField<F>(*mut F);
+
+impl<F> EmplaceField<F> {
+    /// Write `val` to the field.
+    #[doc(hidden)]
+    pub fn write(self, val: F) {
+        if self.0.is_aligned() {
+            unsafe { self.0.write(val) };
+        } else {
+            unsafe { self.0.write_unaligned(val) };
+        }
+    }
+}
+
+impl DynArena {
+    /// Reserve a place for a T to be written by [`emplace!`].
+    #[doc(hidden)]
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn reserve_emplace<T>(&self) -> Option<(EmplacePlace<'_>, *mut T)> {
+        let slot = self.claim_drop_slot_for::<T>()?;
+        let place = self.reserve(size_of::<T>(), align_of::<T>());
+        #[cfg(feature = "record")]
+        self.record::<T>(place.is_some());
+        let place = place?;
+        Some((EmplacePlace { place, slot }, unsafe {
+            self.store().cast::<u8>().add(place).cast()
+        }))
+    }
+
+    /// The field at `field` of a value being written by [`emplace!`].
+    ///
+    /// # Safety
+    /// `field` must point to a field of a place reserved by `reserve_emplace`, and
+    /// nothing else may access the field until it is written.
+    #[doc(hidden)]
+    pub unsafe fn emplace_field<F>(field: *mut F) -> EmplaceField<F> {
+        EmplaceField(field)
+    }
+
+    /// Hand out the T written by [`emplace!`] at `place`, so that the arena drops it.
+    ///
+    /// # Safety
+    /// `place` must have been reserved by `reserve_emplace::<T>` on this arena since
+    /// its last reset and hold a fully initialized T.
+    #[doc(hidden)]
+    #[cfg_attr(feature = "record", track_caller)]
+    pub unsafe fn finish_emplace<T>(&self, place: EmplacePlace<'_>) -> &T {
+        let EmplacePlace { place, mut slot } = place;
+        slot.fill(place, TypeInfo::of::<T>());
+        unsafe { &*self.store().cast::<u8>().add(place).cast::<T>() }
+    }
+}
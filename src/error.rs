@@ -0,0 +1,149 @@
+//! Rich errors and formatted strings kept in an arena, for no_std code without a heap.
+
+use core::{error::Error, fmt, mem::MaybeUninit, ptr::NonNull, slice};
+
+use crate::DynArena;
+
+/// An error acquired from an arena, with a message and an optional source error.
+/// Only ever handed out by reference, as a `&'a ArenaError` that lives as long
+/// as the arena it came from, and usable as a `&dyn Error`.
+// The message and source are held as pointers rather than references so that
+// the type is 'static, which `Error::source` requires of every error in a chain.
+// Since an ArenaError can only be reached through a reference bounded by the
+// arena's lifetime, the pointers are valid for as long as it can be used.
+pub struct ArenaError {
+    message: NonNull<str>,
+    source: Option<NonNull<dyn Error>>,
+}
+
+impl ArenaError {
+    /// The message of the error itself.
+    pub fn message(&self) -> &str {
+        unsafe { self.message.as_ref() }
+    }
+}
+
+impl fmt::Display for ArenaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl fmt::Debug for ArenaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArenaError")
+            .field("message", &self.message())
+            .field("source", &self.source())
+            .finish()
+    }
+}
+
+impl Error for ArenaError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.map(|source| unsafe { source.as_ref() })
+    }
+}
+
+/// A writer that formats a string straight into an arena, handed to the closure
+/// given to [`DynArena::acquire_uformat`]. Writing fails once the arena is full.
+pub struct StrWriter<'w> {
+    buf: &'w mut [MaybeUninit<u8>],
+    len: usize,
+}
+
+impl StrWriter<'_> {
+    /// What has been written so far.
+    pub fn as_str(&self) -> &str {
+        let bytes = unsafe { slice::from_raw_parts(self.buf.as_ptr().cast(), self.len) };
+        unsafe { core::str::from_utf8_unchecked(bytes) }
+    }
+}
+
+impl fmt::Write for StrWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        let dst = self.buf.get_mut(self.len..end).ok_or(fmt::Error)?;
+        for (dst, &byte) in dst.iter_mut().zip(s.as_bytes()) {
+            dst.write(byte);
+        }
+        self.len = end;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uWrite for StrWriter<'_> {
+    type Error = fmt::Error;
+
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        fmt::Write::write_str(self, s)
+    }
+}
+
+impl DynArena {
+    /// acquire a string formatted from `args`, e.g. with `format_args!`.
+    /// While formatting, all of the arena's spare capacity is reserved.
+    pub fn acquire_fmt(&self, args: fmt::Arguments<'_>) -> Option<&str> {
+        self.acquire_written(|writer| fmt::write(writer, args))
+    }
+
+    /// acquire a string written by `f` with ufmt, e.g. `|w| uwrite!(w, "frame {}", seq)`.
+    /// While formatting, all of the arena's spare capacity is reserved.
+    #[cfg(feature = "ufmt")]
+    pub fn acquire_uformat(
+        &self,
+        f: impl FnOnce(&mut StrWriter<'_>) -> fmt::Result,
+    ) -> Option<&str> {
+        self.acquire_written(f)
+    }
+
+    /// acquire a string written by `f` into the arena's spare capacity.
+    fn acquire_written(&self, f: impl FnOnce(&mut StrWriter<'_>) -> fmt::Result) -> Option<&str> {
+        let mut window = self.begin_write(self.spare_capacity())?;
+        let mut writer = StrWriter {
+            buf: window.buf(),
+            len: 0,
+        };
+        f(&mut writer).ok()?;
+        let len = writer.len;
+        let bytes = unsafe { window.commit(len) };
+        Some(unsafe { core::str::from_utf8_unchecked(bytes) })
+    }
+
+    /// acquire an error with a copy of `message`, caused by `source` if there is one.
+    pub fn acquire_error<'a>(
+        &'a self,
+        message: &str,
+        source: Option<&'a (dyn Error + 'static)>,
+    ) -> Option<&'a ArenaError> {
+        let message = self.concat_strs(&[message])?;
+        self.acquire(ArenaError {
+            message: NonNull::from(message),
+            source: source.map(NonNull::from),
+        })
+    }
+
+    /// acquire an error with a message formatted from `args`, caused by `source` if there is one.
+    pub fn acquire_error_fmt<'a>(
+        &'a self,
+        args: fmt::Arguments<'_>,
+        source: Option<&'a (dyn Error + 'static)>,
+    ) -> Option<&'a ArenaError> {
+        let message = self.acquire_fmt(args)?;
+        self.acquire(ArenaError {
+            message: NonNull::from(message),
+            source: source.map(NonNull::from),
+        })
+    }
+
+    /// acquire `err` and an error with a copy of `message` that it caused,
+    /// to add context to an error as it is passed up.
+    pub fn acquire_context<E: Error + 'static>(
+        &self,
+        err: E,
+        message: &str,
+    ) -> Option<&ArenaError> {
+        let source = self.acquire(err)?;
+        self.acquire_error(message, Some(source))
+    }
+}
@@ -0,0 +1,128 @@
+//! A registry of event handlers kept in an arena.
+
+use core::ptr;
+
+use crate::{
+    atomic::{AtomicPtr, Ordering},
+    DynArena,
+};
+
+/// A handler stored in an [`EventBus`].
+type Handler<'a, E> = &'a (dyn Fn(&E) + Sync + 'a);
+
+/// Identifies a handler registered with an [`EventBus`], to unregister it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HandlerId {
+    slot: usize,
+    handler: usize,
+}
+
+/// A fixed number of event handlers for events of type E, stored in an arena.
+///
+/// Handlers can be registered, unregistered and dispatched to through a shared
+/// borrow, e.g. from a `static` bus. The space of an unregistered handler is only
+/// reclaimed when its arena is reset.
+pub struct EventBus<'a, E, const N: usize> {
+    arena: &'a DynArena,
+    // Each slot points to the (fat) reference to its handler, which is kept in the arena too.
+    slots: [AtomicPtr<Handler<'a, E>>; N],
+}
+
+impl<'a, E, const N: usize> EventBus<'a, E, N> {
+    /// Create a new event bus with N handler slots that stores its handlers in `arena`.
+    #[must_use]
+    pub const fn new(arena: &'a DynArena) -> Self {
+        EventBus {
+            arena,
+            slots: [const { AtomicPtr::new(ptr::null_mut()) }; N],
+        }
+    }
+
+    /// acquire `handler` from the arena and register it for every future dispatch.
+    /// Returns None if every slot is taken or the arena is out of space.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn register(&self, handler: impl Fn(&E) + Sync + 'a) -> Option<HandlerId> {
+        // The slot is claimed before the handler is acquired, so that a bus that
+        // fills up in the meantime does not leave the handler behind in the arena.
+        let slot = self.slots.iter().position(|slot| {
+            slot.compare_exchange(
+                ptr::null_mut(),
+                Self::claimed(),
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+        })?;
+        let Some(handler) = self.acquire_handler(handler) else {
+            self.slots[slot].store(ptr::null_mut(), Ordering::Release);
+            return None;
+        };
+        self.slots[slot].store(handler, Ordering::Release);
+        Some(HandlerId {
+            slot,
+            handler: handler.addr(),
+        })
+    }
+
+    /// acquire `handler` and the reference to it that a slot points to.
+    #[cfg_attr(feature = "record", track_caller)]
+    fn acquire_handler(&self, handler: impl Fn(&E) + Sync + 'a) -> Option<*mut Handler<'a, E>> {
+        let handler: Handler<'a, E> = self.arena.acquire(handler)?;
+        Some(ptr::from_ref(self.arena.acquire(handler)?).cast_mut())
+    }
+
+    /// What a slot points to while a handler is being registered in it,
+    /// which is never the address of a handler.
+    fn claimed() -> *mut Handler<'a, E> {
+        ptr::dangling_mut()
+    }
+
+    /// Unregister a handler so it is no longer dispatched to.
+    /// Returns whether it was still registered.
+    pub fn unregister(&self, id: HandlerId) -> bool {
+        let slot = &self.slots[id.slot];
+        let handler = slot.load(Ordering::Acquire);
+        handler.addr() == id.handler
+            && slot
+                .compare_exchange(
+                    handler,
+                    ptr::null_mut(),
+                    Ordering::AcqRel,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+    }
+
+    /// Call every registered handler with `event`, in slot order.
+    /// Returns the number of handlers called.
+    pub fn dispatch(&self, event: &E) -> usize {
+        let mut called = 0;
+        for slot in &self.slots {
+            let handler = slot.load(Ordering::Acquire);
+            if handler == Self::claimed() {
+                continue;
+            }
+            if let Some(handler) = unsafe { handler.as_ref() } {
+                handler(event);
+                called += 1;
+            }
+        }
+        called
+    }
+
+    /// The number of handlers registered.
+    pub fn len(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| {
+                let handler = slot.load(Ordering::Acquire);
+                !handler.is_null() && handler != Self::claimed()
+            })
+            .count()
+    }
+
+    /// Whether no handlers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
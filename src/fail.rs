@@ -0,0 +1,34 @@
+//! Failing chosen allocations on purpose, to test the code that handles an arena
+//! running out of room.
+
+use core::ptr;
+
+use crate::{atomic::Ordering, DynArena};
+
+impl DynArena {
+    /// Make the `n`th allocation from now fail as if the arena were full, counting
+    /// from one; zero cancels it. Every acquire counts, including those of zero
+    /// sized values, so that which one fails does not depend on the arena's state.
+    pub fn fail_nth(&self, n: usize) {
+        self.fail_countdown.store(n, Ordering::Relaxed);
+    }
+
+    /// Make every allocation of a number of bytes for which `pred` returns true fail
+    /// as if the arena were full, e.g. `|size| size > 64`; None cancels it.
+    pub fn fail_if(&self, pred: Option<fn(usize) -> bool>) {
+        let pred = pred.map_or(ptr::null_mut(), |pred| pred as *mut ());
+        self.fail_if.store(pred, Ordering::Relaxed);
+    }
+
+    /// Whether an allocation of `size` bytes is to fail.
+    pub(crate) fn injected_failure(&self, size: usize) -> bool {
+        let nth = self
+            .fail_countdown
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok_and(|n| n == 1);
+        let pred = self.fail_if.load(Ordering::Relaxed);
+        // Only ever stored from a `fn(usize) -> bool` by `fail_if`.
+        nth || !pred.is_null()
+            && unsafe { core::mem::transmute::<*mut (), fn(usize) -> bool>(pred) }(size)
+    }
+}
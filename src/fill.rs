@@ -0,0 +1,45 @@
+//! A byte that debug builds fill fresh allocations with, so that reads of memory
+//! nothing has written yet give obviously wrong values rather than zeros that
+//! hide the bug.
+
+#[cfg(debug_assertions)]
+use crate::atomic::Ordering;
+use crate::DynArena;
+
+impl DynArena {
+    /// Fill every allocation with `byte` before handing it out, e.g. 0xAA during
+    /// bring-up; None stops filling. Only debug builds fill, so this costs release
+    /// builds nothing.
+    ///
+    /// Under Valgrind the filled bytes count as initialized, so reads of them are no
+    /// longer reported.
+    pub fn set_fill(&self, byte: Option<u8>) {
+        #[cfg(debug_assertions)]
+        self.fill.store(
+            byte.map_or(0, |byte| usize::from(byte) + 1),
+            Ordering::Relaxed,
+        );
+        #[cfg(not(debug_assertions))]
+        let _ = byte;
+    }
+
+    /// The byte allocations are filled with, which is always None in release builds.
+    pub fn fill(&self) -> Option<u8> {
+        #[cfg(debug_assertions)]
+        return self
+            .fill
+            .load(Ordering::Relaxed)
+            .checked_sub(1)
+            .map(|byte| byte as u8);
+        #[cfg(not(debug_assertions))]
+        return None;
+    }
+
+    /// Fill the `len` bytes reserved at `place` with the fill byte, if there is one.
+    #[cfg(debug_assertions)]
+    pub(crate) fn fill_reserved(&self, place: usize, len: usize) {
+        if let Some(byte) = self.fill() {
+            unsafe { self.store().cast::<u8>().add(place).write_bytes(byte, len) };
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Cleanup that runs when an arena is torn down, for things no single value owns.
+
+use core::mem::ManuallyDrop;
+
+use crate::{atomic::Ordering, drop_panic::DropPass, DynArena};
+
+/// The part of a finalizer that is the same for every closure type, which the
+/// arena keeps a list of.
+#[repr(C)]
+struct Node {
+    /// One past the offset of the finalizer registered before this one, or zero.
+    next: usize,
+    run: unsafe fn(*mut Node),
+}
+
+/// A finalizer and the closure it runs, kept in the backing store of the arena.
+#[repr(C)]
+struct Finalizer<F> {
+    node: Node,
+    f: ManuallyDrop<F>,
+}
+
+impl DynArena {
+    /// Register `f` to be called once when the arena is dropped, e.g. to flush a
+    /// log region or release a peripheral, handing it back if the arena has no
+    /// room for it.
+    ///
+    /// `f` is kept in the arena itself and runs before any value in the arena is
+    /// dropped, the last registered first. Resetting the arena also calls it, as
+    /// does the end of a [`DynArena::scope`] it was registered in, since either
+    /// takes back the room it is kept in.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn on_drop<F: FnOnce() + 'static>(&self, f: F) -> Result<(), F> {
+        let Some((place, ptr)) = self.get_ptr_place::<Finalizer<F>>() else {
+            return Err(f);
+        };
+        let finalizer = ptr.write(Finalizer {
+            node: Node {
+                next: 0,
+                run: |node| unsafe {
+                    ManuallyDrop::take(&mut (*node.cast::<Finalizer<F>>()).f)();
+                },
+            },
+            f: ManuallyDrop::new(f),
+        });
+        let _ = self
+            .finalizers
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |next| {
+                finalizer.node.next = next;
+                Some(place + 1)
+            });
+        Ok(())
+    }
+
+    /// Call, and forget, every finalizer kept at or past offset `store`, as part of `pass`.
+    ///
+    /// # Safety
+    /// No finalizer may be registered at the same time.
+    pub(crate) unsafe fn run_finalizers(&self, store: usize, pass: &DropPass<'_>) {
+        loop {
+            let head = self.finalizers.load(Ordering::Acquire);
+            if head == 0 || head - 1 < store {
+                return;
+            }
+            let node = unsafe { self.store().cast::<u8>().add(head - 1).cast::<Node>() };
+            // Unlinked before it runs, so that it runs once even if it panics.
+            self.finalizers
+                .store(unsafe { (*node).next }, Ordering::Release);
+            pass.call(|| unsafe { ((*node).run)(node) });
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! A double-buffered arena for per-frame allocation.
+
+use crate::{
+    atomic::{AtomicUsize, Ordering},
+    CountedArena, CountedRef, Init,
+};
+
+/// Two arenas that take turns serving allocations, one frame at a time.
+///
+/// Flipping resets the arena holding the frame before last and makes it the
+/// current one, so references into the last frame stay readable while the
+/// new frame allocates.
+pub struct FrameArena<const SIZE: usize> {
+    arenas: [CountedArena<SIZE>; 2],
+    current: AtomicUsize,
+}
+
+impl<const SIZE: usize> Default for FrameArena<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize> FrameArena<SIZE> {
+    /// Create a new frame arena with two fixed size buffers of SIZE bytes.
+    #[must_use]
+    pub const fn new() -> Self {
+        FrameArena {
+            arenas: [CountedArena::new(), CountedArena::new()],
+            current: AtomicUsize::new(0),
+        }
+    }
+
+    /// The arena serving allocations for the current frame.
+    pub fn current(&self) -> &CountedArena<SIZE> {
+        &self.arenas[self.current.load(Ordering::Acquire)]
+    }
+
+    /// The arena holding the allocations of the last frame.
+    pub fn previous(&self) -> &CountedArena<SIZE> {
+        &self.arenas[1 - self.current.load(Ordering::Acquire)]
+    }
+
+    /// The number of times the frame arena has been flipped.
+    pub fn generation(&self) -> usize {
+        self.arenas[0]
+            .generation()
+            .wrapping_add(self.arenas[1].generation())
+    }
+
+    /// Start a new frame if nothing from the frame before last is still referenced.
+    /// Returns whether the flip happened.
+    pub fn try_flip(&self) -> bool {
+        let current = self.current.load(Ordering::Acquire);
+        let next = 1 - current;
+        if !self.arenas[next].try_reset() {
+            return false;
+        }
+        self.current
+            .compare_exchange(current, next, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// Start a new frame, dropping everything from the frame before last.
+    pub fn flip(&mut self) {
+        let next = 1 - *self.current.get_mut();
+        self.arenas[next].reset();
+        *self.current.get_mut() = next;
+    }
+
+    /// acquire a reference in the current frame to a value of type T that can be
+    /// initialized with the Init trait, using the default value of the InitArg.
+    pub fn acquire_init_default<T: Init>(&self) -> Option<CountedRef<'_, T>>
+    where
+        T::InitArg: Default,
+    {
+        self.current().acquire_init_default()
+    }
+
+    /// acquire a reference in the current frame to a value of type T that can be
+    /// initialized with the Init trait, using a given InitArg.
+    pub fn acquire_init<T: Init>(&self, arg: T::InitArg) -> Option<CountedRef<'_, T>> {
+        self.current().acquire_init(arg)
+    }
+
+    /// acquire a reference in the current frame to a value of type T that is
+    /// initialized with it's default value.
+    pub fn acquire_default<T: Default>(&self) -> Option<CountedRef<'_, T>> {
+        self.current().acquire_default()
+    }
+
+    /// acquire a reference in the current frame to a value of type T that is
+    /// initialized with the given value.
+    pub fn acquire<T>(&self, val: T) -> Option<CountedRef<'_, T>> {
+        self.current().acquire(val)
+    }
+}
@@ -0,0 +1,75 @@
+//! Values materialized from raw bytes, checked by zerocopy to be valid for any bytes.
+//!
+//! Byte buffers from [`DynArena::acquire_bytes`] are not aligned, so zerocopy can
+//! only view them as types without alignment. These place the bytes where a T can
+//! live instead, e.g. for a register block captured from a peripheral or a wire
+//! struct read off a bus.
+
+use core::{
+    mem::{align_of, size_of},
+    slice,
+};
+
+use zerocopy::{FromBytes, FromZeros};
+
+use crate::{DynArena, TypeInfo};
+
+impl DynArena {
+    /// acquire a T holding a copy of `bytes`, which need not be aligned.
+    /// Returns None if `bytes` is not exactly the size of a T or the arena has no room.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_from_bytes<T: FromBytes>(&self, bytes: &[u8]) -> Option<&T> {
+        if bytes.len() != size_of::<T>() {
+            return None;
+        }
+        let mut slot = self.claim_drop_slot_for::<T>()?;
+        let place = self.reserve(size_of::<T>(), align_of::<T>());
+        #[cfg(feature = "record")]
+        self.record::<T>(place.is_some());
+        let place = place?;
+
+        let ptr = unsafe { self.store().cast::<u8>().add(place) };
+        unsafe { ptr.copy_from_nonoverlapping(bytes.as_ptr(), bytes.len()) };
+        slot.fill(place, TypeInfo::of::<T>());
+        Some(unsafe { &*ptr.cast::<T>() })
+    }
+
+    /// acquire a slice of Ts holding a copy of `bytes`, which need not be aligned.
+    /// Returns None if the length of `bytes` is not a multiple of the size of a T
+    /// or the arena has no room.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_slice_from_bytes<T: FromBytes>(&self, bytes: &[u8]) -> Option<&[T]> {
+        let len = bytes.len().checked_div(size_of::<T>()).unwrap_or(0);
+        if len * size_of::<T>() != bytes.len() {
+            return None;
+        }
+        let mut slot = self.claim_slice_drop_slots::<T>(1)?;
+        let (place, start) = self.reserve_slice::<T>(len)?;
+        unsafe {
+            start
+                .cast::<u8>()
+                .copy_from_nonoverlapping(bytes.as_ptr(), bytes.len())
+        };
+        self.add_slice_to_drop_queue::<T>(&mut slot, place, len);
+        Some(unsafe { slice::from_raw_parts(start, len) })
+    }
+
+    /// acquire a T with every byte zero, to be filled in place through
+    /// `zerocopy::IntoBytes::as_mut_bytes`, e.g. by a DMA transfer, and then read as a T.
+    // Every call reserves a disjoint region of the backing store, so handing out
+    // a mutable reference to it from a shared borrow of the arena is sound.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_zeroed<T: FromZeros>(&self) -> Option<&mut T> {
+        let mut slot = self.claim_drop_slot_for::<T>()?;
+        let place = self.reserve(size_of::<T>(), align_of::<T>());
+        #[cfg(feature = "record")]
+        self.record::<T>(place.is_some());
+        let place = place?;
+
+        let ptr = unsafe { self.store().cast::<u8>().add(place) };
+        unsafe { ptr.write_bytes(0, size_of::<T>()) };
+        slot.fill(place, TypeInfo::of::<T>());
+        Some(unsafe { &mut *ptr.cast::<T>() })
+    }
+}
@@ -0,0 +1,56 @@
+//! Values generated with `arbitrary` straight into an arena, for fuzzers that
+//! build structured input for code working on arena data.
+
+use core::mem::size_of;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{DynArena, Reservation, TypeInfo};
+
+impl DynArena {
+    /// acquire a T generated from the fuzzer input in `u`.
+    ///
+    /// Returns None if the arena has no room for a T, before any input is taken,
+    /// and Some(Err) if `u` cannot make one, in which case the room is handed back.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_arbitrary<'u, T: Arbitrary<'u>>(
+        &self,
+        u: &mut Unstructured<'u>,
+    ) -> Option<Result<&T>> {
+        let mut slot = self.claim_drop_slot_for::<T>()?;
+        let (place, ptr) = self.get_ptr_place::<T>()?;
+        // Handed back if generating fails or panics.
+        let reservation = Reservation::new(self, place, size_of::<T>());
+        let val = match T::arbitrary(u) {
+            Ok(val) => ptr.write(val),
+            Err(err) => return Some(Err(err)),
+        };
+        reservation.keep();
+        slot.fill(place, TypeInfo::of::<T>());
+        Some(Ok(&*val))
+    }
+
+    /// acquire a slice of Ts generated from the fuzzer input in `u`, as long as `u`
+    /// picks.
+    ///
+    /// Returns None if the arena has no room for the slice, and Some(Err) if `u`
+    /// cannot make one of its values, in which case those made are dropped.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_arbitrary_slice<'u, T: Arbitrary<'u>>(
+        &self,
+        u: &mut Unstructured<'u>,
+    ) -> Option<Result<&[T]>> {
+        let len = match u.arbitrary_len::<T>() {
+            Ok(len) => len,
+            Err(err) => return Some(Err(err)),
+        };
+        let mut failed = None;
+        let slice = self.acquire_slice_try_fill_with(len, |_| {
+            T::arbitrary(u).map_err(|err| failed = Some(err)).ok()
+        });
+        match (slice, failed) {
+            (_, Some(err)) => Some(Err(err)),
+            (slice, None) => slice.map(|slice| Ok(&*slice)),
+        }
+    }
+}
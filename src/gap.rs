@@ -0,0 +1,190 @@
+//! Editable text kept in an arena.
+
+use core::{
+    fmt,
+    ops::{Bound, RangeBounds},
+    str,
+};
+
+use crate::DynArena;
+
+/// Text in an arena that can be edited in place, for line editors and config
+/// editing without `alloc`.
+///
+/// The text is kept in one buffer with a gap at the last edit, so edits near
+/// each other only move the bytes between them. Positions are byte offsets and
+/// must fall on char boundaries.
+///
+/// When the gap fills up, a buffer twice the size is acquired; the space of the
+/// old one is only reclaimed when the arena is reset.
+pub struct GapBuffer<'a> {
+    arena: &'a DynArena,
+    // Always UTF-8 before the gap and after it.
+    buf: &'a mut [u8],
+    gap_start: usize,
+    gap_end: usize,
+}
+
+impl<'a> GapBuffer<'a> {
+    /// acquire an empty buffer with room for `capacity` bytes of text from `arena`.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn new(arena: &'a DynArena, capacity: usize) -> Option<Self> {
+        let buf = arena.acquire_slice_try_fill_with(capacity, |_| Some(0))?;
+        Some(GapBuffer {
+            arena,
+            buf,
+            gap_start: 0,
+            gap_end: capacity,
+        })
+    }
+
+    /// The length of the text in bytes.
+    pub fn len(&self) -> usize {
+        self.buf.len() - (self.gap_end - self.gap_start)
+    }
+
+    /// Whether there is no text.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of bytes of text the buffer can hold before it has to grow.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Whether `pos` is a char boundary of the text.
+    pub fn is_char_boundary(&self, pos: usize) -> bool {
+        let byte = if pos < self.gap_start {
+            self.buf[pos]
+        } else if pos < self.len() {
+            self.buf[pos - self.gap_start + self.gap_end]
+        } else {
+            return pos == self.len();
+        };
+        // Not a continuation byte.
+        (byte as i8) >= -0x40
+    }
+
+    /// The start and end of `range`, if they are char boundaries in order.
+    fn bounds(&self, range: impl RangeBounds<usize>) -> Option<(usize, usize)> {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.checked_add(1)?,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.checked_add(1)?,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        (start <= end && self.is_char_boundary(start) && self.is_char_boundary(end))
+            .then_some((start, end))
+    }
+
+    /// Move the gap to start at `pos`.
+    fn move_gap(&mut self, pos: usize) {
+        if pos < self.gap_start {
+            let moved = self.gap_start - pos;
+            self.buf
+                .copy_within(pos..self.gap_start, self.gap_end - moved);
+            self.gap_start = pos;
+            self.gap_end -= moved;
+        } else if pos > self.gap_start {
+            let moved = pos - self.gap_start;
+            self.buf
+                .copy_within(self.gap_end..self.gap_end + moved, self.gap_start);
+            self.gap_start = pos;
+            self.gap_end += moved;
+        }
+    }
+
+    /// Make the gap at least `additional` bytes long, acquiring a bigger buffer if it is not.
+    #[cfg_attr(feature = "record", track_caller)]
+    fn reserve(&mut self, additional: usize) -> bool {
+        if self.gap_end - self.gap_start >= additional {
+            return true;
+        }
+        let Some(capacity) = self.len().checked_add(additional) else {
+            return false;
+        };
+        let capacity = capacity.max(self.buf.len().saturating_mul(2));
+        let Some(buf) = self
+            .arena
+            .acquire_slice_try_fill_with(capacity, |_| Some(0))
+        else {
+            return false;
+        };
+        let after = self.buf.len() - self.gap_end;
+        buf[..self.gap_start].copy_from_slice(&self.buf[..self.gap_start]);
+        buf[capacity - after..].copy_from_slice(&self.buf[self.gap_end..]);
+        self.gap_end = capacity - after;
+        self.buf = buf;
+        true
+    }
+
+    /// Insert `text` at `pos`.
+    /// Returns whether `pos` was a char boundary and there was room for the text.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn insert(&mut self, pos: usize, text: &str) -> bool {
+        if !self.is_char_boundary(pos) || !self.reserve(text.len()) {
+            return false;
+        }
+        self.move_gap(pos);
+        self.buf[pos..pos + text.len()].copy_from_slice(text.as_bytes());
+        self.gap_start += text.len();
+        true
+    }
+
+    /// Add `text` to the end.
+    /// Returns whether there was room for it.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn push_str(&mut self, text: &str) -> bool {
+        self.insert(self.len(), text)
+    }
+
+    /// Remove the text in `range`.
+    /// Returns whether its ends were char boundaries.
+    pub fn delete(&mut self, range: impl RangeBounds<usize>) -> bool {
+        let Some((start, end)) = self.bounds(range) else {
+            return false;
+        };
+        self.move_gap(start);
+        self.gap_end += end - start;
+        true
+    }
+
+    /// The text in `range`, if its ends are char boundaries.
+    /// Moves the gap out of the way if it is in the middle of the range.
+    pub fn slice(&mut self, range: impl RangeBounds<usize>) -> Option<&str> {
+        let (start, end) = self.bounds(range)?;
+        if start < self.gap_start && self.gap_start < end {
+            self.move_gap(end);
+        }
+        let bytes = if end <= self.gap_start {
+            &self.buf[start..end]
+        } else {
+            let gap = self.gap_end - self.gap_start;
+            &self.buf[start + gap..end + gap]
+        };
+        Some(unsafe { str::from_utf8_unchecked(bytes) })
+    }
+
+    /// The text before the gap and the text after it.
+    pub fn as_strs(&self) -> (&str, &str) {
+        unsafe {
+            (
+                str::from_utf8_unchecked(&self.buf[..self.gap_start]),
+                str::from_utf8_unchecked(&self.buf[self.gap_end..]),
+            )
+        }
+    }
+}
+
+impl fmt::Display for GapBuffer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (before, after) = self.as_strs();
+        f.write_str(before)?;
+        f.write_str(after)
+    }
+}
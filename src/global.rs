@@ -0,0 +1,51 @@
+//! A default arena for the whole program, and free functions that acquire from it.
+
+use crate::DynArena;
+
+/// Declare the program's default arena, of `$size` bytes, which [`arena_alloc`]
+/// and [`arena_alloc_str`] acquire from, e.g. for a small application or an
+/// example that would rather not pass `&ARENA` to everything.
+///
+/// Declare it once, in the binary; using the free functions without it fails to link.
+///
+/// ```
+/// use arena_alloc::{arena_alloc, arena_alloc_str, default_arena};
+///
+/// default_arena!(1024);
+///
+/// let answer = arena_alloc(42).unwrap();
+/// let name = arena_alloc_str("sensor-7").unwrap();
+/// assert_eq!((*answer, name), (42, "sensor-7"));
+/// ```
+#[macro_export]
+macro_rules! default_arena {
+    ($size:expr) => {
+        #[export_name = "__arena_alloc_default_arena"]
+        fn __arena_alloc_default_arena() -> &'static $crate::DynArena {
+            static ARENA: $crate::Arena<{ $size }> = $crate::Arena::new();
+            &ARENA
+        }
+    };
+}
+
+extern "Rust" {
+    // Defined by `default_arena!`.
+    #[link_name = "__arena_alloc_default_arena"]
+    fn default_arena() -> &'static DynArena;
+}
+
+/// acquire a reference to a value of type T that is initialized with the given value,
+/// from the arena declared with [`default_arena!`].
+// Inlined so that a program that never calls it does not need a default arena to link.
+#[inline]
+#[cfg_attr(feature = "record", track_caller)]
+pub fn arena_alloc<T>(val: T) -> Option<&'static T> {
+    unsafe { default_arena() }.acquire(val)
+}
+
+/// acquire a copy of `s` from the arena declared with [`default_arena!`].
+#[inline]
+#[cfg_attr(feature = "record", track_caller)]
+pub fn arena_alloc_str(s: &str) -> Option<&'static str> {
+    unsafe { default_arena() }.concat_strs(&[s])
+}
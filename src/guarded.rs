@@ -0,0 +1,121 @@
+//! Arenas backed by mapped memory with inaccessible guard pages, for catching overruns during host testing.
+
+use core::{mem, ops::Deref, ptr::NonNull};
+use std::io;
+
+use crate::{sys, Arena};
+
+/// A mapping made by [`sys::map`] with a guard page at its end.
+struct Mapping {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+impl Mapping {
+    /// Map `usable` bytes rounded up to whole pages, followed by a guard page.
+    /// Returns the mapping and the end of its usable part.
+    fn new(usable: usize) -> io::Result<(Self, NonNull<u8>)> {
+        let page = sys::page_size();
+        let usable = usable.max(1).next_multiple_of(page);
+        let ptr = sys::map(usable + page)?;
+        let mapping = Mapping {
+            ptr,
+            len: usable + page,
+        };
+        let guard = unsafe { ptr.add(usable) };
+        unsafe { sys::protect_none(guard, page)? };
+        Ok((mapping, guard))
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { sys::unmap(self.ptr, self.len) };
+    }
+}
+
+/// A value allocated on its own pages, right before a guard page.
+struct Isolated {
+    _mapping: Mapping,
+    place: NonNull<u8>,
+    drop_func: unsafe fn(NonNull<u8>),
+}
+
+impl Drop for Isolated {
+    fn drop(&mut self) {
+        unsafe { (self.drop_func)(self.place) };
+    }
+}
+
+unsafe impl Send for Isolated {}
+unsafe impl Sync for Isolated {}
+
+/// An arena placed in mapped memory so that its backing store ends right at an
+/// inaccessible guard page (give or take the padding that rounds SIZE up to 16 bytes).
+/// Writing past the end of the last allocation crashes instead of corrupting
+/// whatever lies after the arena.
+///
+/// Derefs to the [`Arena`] it contains.
+pub struct GuardedArena<const SIZE: usize> {
+    _mapping: Mapping,
+    arena: NonNull<Arena<SIZE>>,
+}
+
+unsafe impl<const SIZE: usize> Sync for GuardedArena<SIZE> {}
+unsafe impl<const SIZE: usize> Send for GuardedArena<SIZE> {}
+
+impl<const SIZE: usize> GuardedArena<SIZE> {
+    /// Map a new arena with a fixed size buffer of SIZE bytes followed by a guard page.
+    pub fn new() -> io::Result<Self> {
+        let (mapping, guard) = Mapping::new(mem::size_of::<Arena<SIZE>>())?;
+        let arena = unsafe {
+            guard
+                .sub(mem::size_of::<Arena<SIZE>>())
+                .cast::<Arena<SIZE>>()
+        };
+        unsafe { arena.write(Arena::new()) };
+        Ok(GuardedArena {
+            _mapping: mapping,
+            arena,
+        })
+    }
+
+    /// acquire a reference to a value of type T that is initialized with the given value,
+    /// placed on its own pages so that it ends right at a guard page.
+    pub fn acquire_isolated<T>(&self, val: T) -> Option<&T> {
+        let (mapping, guard) = Mapping::new(mem::size_of::<T>()).ok()?;
+        let place = (guard.as_ptr().addr() - mem::size_of::<T>()) & !(mem::align_of::<T>() - 1);
+        let place = unsafe { guard.sub(guard.as_ptr().addr() - place) };
+        unsafe { place.cast::<T>().write(val) };
+
+        let isolated = Isolated {
+            _mapping: mapping,
+            place,
+            drop_func: |place| unsafe { place.cast::<T>().drop_in_place() },
+        };
+        self.acquire(isolated)?;
+
+        Some(unsafe { place.cast::<T>().as_ref() })
+    }
+
+    /// Touch every page of the arena up front, as [`DynArena::prefault`] does.
+    ///
+    /// [`DynArena::prefault`]: crate::DynArena::prefault
+    pub fn prefault(&mut self) {
+        unsafe { self.arena.as_mut() }.prefault();
+    }
+}
+
+impl<const SIZE: usize> Deref for GuardedArena<SIZE> {
+    type Target = Arena<SIZE>;
+
+    fn deref(&self) -> &Arena<SIZE> {
+        unsafe { self.arena.as_ref() }
+    }
+}
+
+impl<const SIZE: usize> Drop for GuardedArena<SIZE> {
+    fn drop(&mut self) {
+        unsafe { self.arena.drop_in_place() };
+    }
+}
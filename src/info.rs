@@ -0,0 +1,162 @@
+//! Introspection of the allocations an arena holds.
+
+use core::{fmt, slice};
+
+#[cfg(feature = "call-sites")]
+use core::panic::Location;
+
+use crate::{atomic::Ordering, DropSlot, Dropper, DynArena};
+
+impl DynArena {
+    /// The drop queue entries made so far, once no acquisition is halfway through writing one.
+    pub(crate) fn published_droppers(&self) -> &[DropSlot] {
+        loop {
+            let len = self.next_free_drop_spot.load(Ordering::Acquire);
+            let published = self.published_drop_spots.load(Ordering::Acquire);
+            if published == len && self.next_free_drop_spot.load(Ordering::Acquire) == len {
+                let drop_queue = self.storage.drop_queue();
+                return unsafe {
+                    slice::from_raw_parts(drop_queue.cast(), len.min(drop_queue.len()))
+                };
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// The drop queue entries made so far that still hold a destructor.
+    pub(crate) fn live_droppers(&self) -> impl Iterator<Item = &Dropper> {
+        self.published_droppers()
+            .iter()
+            .filter_map(|slot| slot.0.as_ref())
+    }
+
+    /// The size of the value `dropper` drops, not counting a slice's length header.
+    fn size_of_value(&self, dropper: &Dropper) -> usize {
+        let ptr = unsafe { self.store().cast::<u8>().add(dropper.place) };
+        (dropper.info.size)(ptr)
+    }
+
+    /// Call `f` with the offset, size and type name of every value the arena still holds,
+    /// in the order they were acquired.
+    /// Slices of types that need no dropping are not tracked.
+    // Values need not be 'static, so they are identified by type name rather than TypeId.
+    pub fn for_each_allocation(&self, mut f: impl FnMut(usize, usize, &'static str)) {
+        for dropper in self.live_droppers() {
+            f(
+                dropper.place,
+                self.size_of_value(dropper),
+                (dropper.info.type_name)(),
+            );
+        }
+    }
+
+    /// Write the offset, size and type name of every value the arena still holds, one per line.
+    /// With the `call-sites` feature each line ends with where the value was acquired.
+    pub fn report_live(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        for dropper in self.live_droppers() {
+            let (place, size) = (dropper.place, self.size_of_value(dropper));
+            write!(w, "{place:#06x} {size:>6} {}", (dropper.info.type_name)())?;
+            #[cfg(feature = "call-sites")]
+            write!(w, " at {}", dropper.location)?;
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+
+    /// Where the value at `offset` bytes into the arena was acquired, if the arena
+    /// still holds one there, e.g. to find the source of a value a debugger points at.
+    /// Slices of types that need no dropping, and zero-sized values that need none,
+    /// are not tracked.
+    #[cfg(feature = "call-sites")]
+    pub fn acquired_at(&self, offset: usize) -> Option<&'static Location<'static>> {
+        self.live_droppers()
+            .filter(|dropper| dropper.place == offset)
+            .map(|dropper| dropper.location)
+            .last()
+    }
+
+    /// Write a map of the arena in address order, one line per value it still holds
+    /// with its offset, size in bytes (including the length header of a slice), the
+    /// alignment padding before it and its type name, then a line for the free space.
+    ///
+    /// With the `call-sites` feature each value's line ends with where it was acquired.
+    /// Bytes held by no tracked value, such as slices of types that need no dropping,
+    /// byte buffers and padding beyond what alignment needs, get an `(untracked)` line.
+    // The drop queue is in acquisition order, which concurrent acquisitions can leave
+    // out of address order, and there is nowhere to sort it, so the next value is
+    // found by a scan of the whole queue.
+    pub fn dump_map(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        let droppers = self.published_droppers();
+        let base = self.store().cast::<u8>().addr();
+        writeln!(w, "offset     size   pad  type")?;
+        let mut end = 0;
+        let mut last = None;
+        loop {
+            let next = droppers
+                .iter()
+                .enumerate()
+                .filter_map(|(i, slot)| {
+                    slot.0.as_ref().map(|dropper| ((dropper.place, i), dropper))
+                })
+                .filter(|(key, _)| last.is_none_or(|last| *key > last))
+                .min_by_key(|(key, _)| *key);
+            let Some((key, dropper)) = next else {
+                break;
+            };
+            last = Some(key);
+            let place = dropper.place;
+            let aligned = (base + end).next_multiple_of(dropper.info.align) - base;
+            let pad = if place == aligned {
+                aligned - end
+            } else {
+                writeln!(
+                    w,
+                    "{end:#06x} {:>8}     -  (untracked)",
+                    place.saturating_sub(end)
+                )?;
+                0
+            };
+            let size = dropper.info.header + self.size_of_value(dropper);
+            write!(
+                w,
+                "{place:#06x} {size:>8} {pad:>5}  {}",
+                (dropper.info.type_name)()
+            )?;
+            #[cfg(feature = "call-sites")]
+            write!(w, " at {}", dropper.location)?;
+            writeln!(w)?;
+            end = end.max(place + size);
+        }
+        let used = self.next_free_store_spot.load(Ordering::Acquire);
+        if used > end {
+            writeln!(w, "{end:#06x} {:>8}     -  (untracked)", used - end)?;
+        }
+        let used = used.max(end);
+        writeln!(w, "{used:#06x} {:>8}     -  (free)", self.capacity() - used)
+    }
+
+    /// Report the values the arena still holds on stderr, if there are any.
+    #[cfg(feature = "leak-report")]
+    pub(crate) fn report_live_on_drop(&self) {
+        struct Stderr;
+
+        impl fmt::Write for Stderr {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                use std::io::Write;
+                std::io::stderr()
+                    .write_all(s.as_bytes())
+                    .map_err(|_| fmt::Error)
+            }
+        }
+
+        let live = self.published_droppers().len();
+        if live == 0 {
+            return;
+        }
+        std::eprintln!(
+            "arena of {} bytes dropped with {live} live allocations:",
+            self.capacity()
+        );
+        let _ = self.report_live(&mut Stderr);
+    }
+}
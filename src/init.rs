@@ -1,9 +1,16 @@
-//! Initialization trait for types that require a circular reference to themselves upon initialization.
+//! Initialization trait for types that require a circular reference to themselves upon initialization,
+//! and initialization of values in place in the arena.
 
-use core::mem::MaybeUninit;
+use core::mem::{size_of, MaybeUninit};
+
+use crate::{DynArena, Reservation, TypeInfo};
 
 /// A trait for initialization of a type that is stored in an arena and
 /// requires a circular reference to itself to initialize.
+///
+/// Once a reference to `me` has been taken, the rest of the value must be written
+/// through a pointer the reference was derived from, e.g. `me.as_mut_ptr()`, since
+/// writing through `me` itself invalidates the reference.
 pub trait Init {
     type InitArg;
 
@@ -11,3 +18,45 @@ pub trait Init {
     where
         Self: Sized;
 }
+
+impl DynArena {
+    /// acquire a reference to a value of type T that `f` initializes where it lies in the
+    /// arena, so that the value is never built on the stack and copied in, as it may be
+    /// with [`DynArena::acquire`], e.g. for a buffer of several kilobytes on a small task stack.
+    ///
+    /// Returns None if the arena has no room for a T, and the error of `f` if it fails,
+    /// in which case the space is handed back if nothing has been acquired since.
+    ///
+    /// ```
+    /// use arena_alloc::Arena;
+    ///
+    /// static ARENA: Arena<32768> = Arena::new();
+    ///
+    /// let buf = unsafe {
+    ///     ARENA.acquire_in_place::<[u8; 16384], ()>(|slot| {
+    ///         slot.as_mut_ptr().write_bytes(0, 1);
+    ///         Ok(())
+    ///     })
+    /// };
+    /// assert!(buf.unwrap().unwrap().iter().all(|b| *b == 0));
+    /// ```
+    ///
+    /// # Safety
+    /// If `f` returns Ok, it must have fully initialized the value.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub unsafe fn acquire_in_place<T, E>(
+        &self,
+        f: impl FnOnce(&mut MaybeUninit<T>) -> Result<(), E>,
+    ) -> Option<Result<&T, E>> {
+        let mut slot = self.claim_drop_slot_for::<T>()?;
+        let (place, ptr) = self.get_ptr_place::<T>()?;
+        // Handed back if `f` fails or panics.
+        let reservation = Reservation::new(self, place, size_of::<T>());
+        if let Err(err) = f(ptr) {
+            return Some(Err(err));
+        }
+        reservation.keep();
+        slot.fill(place, TypeInfo::of::<T>());
+        Some(Ok(unsafe { ptr.assume_init_ref() }))
+    }
+}
@@ -0,0 +1,193 @@
+//! Reading and writing bytes of unknown length straight into an arena.
+
+#[cfg(feature = "embedded-io")]
+use core::fmt;
+use core::{
+    mem::{self, MaybeUninit},
+    slice,
+};
+
+use crate::{annotate, atomic::Ordering, DynArena, CANARY};
+
+/// Why [`DynArena::acquire_read`] failed.
+#[cfg(feature = "embedded-io")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReadError<E> {
+    /// The arena did not have room for the requested number of bytes.
+    OutOfSpace,
+    /// The source failed to read.
+    Read(E),
+}
+
+#[cfg(feature = "embedded-io")]
+impl<E: fmt::Display> fmt::Display for ReadError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::OutOfSpace => f.write_str("arena is out of space"),
+            ReadError::Read(e) => write!(f, "read failed: {e}"),
+        }
+    }
+}
+
+/// Room for up to some number of bytes reserved in an arena, to be written and
+/// then committed, e.g. as the target of a DMA transfer or an encoder of unknown output size.
+///
+/// Dropping the window without committing it hands all of its room back.
+pub struct WriteWindow<'a> {
+    arena: &'a DynArena,
+    place: usize,
+    len: usize,
+}
+
+impl<'a> WriteWindow<'a> {
+    /// The room to write into.
+    pub fn buf(&mut self) -> &mut [MaybeUninit<u8>] {
+        unsafe { slice::from_raw_parts_mut(self.start().cast(), self.len) }
+    }
+
+    /// The number of bytes that can be written.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there is no room to write into.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Keep the first `written` bytes and hand the rest of the room back,
+    /// unless something else was acquired since the window was opened.
+    ///
+    /// # Safety
+    /// The first `written` bytes must have been initialized.
+    pub unsafe fn commit(self, written: usize) -> &'a [u8] {
+        let written = written.min(self.len);
+        self.arena.shrink(self.place, self.len, written);
+        let start = self.start();
+        mem::forget(self);
+        unsafe { slice::from_raw_parts(start, written) }
+    }
+
+    fn start(&self) -> *mut u8 {
+        unsafe { self.arena.store().cast::<u8>().add(self.place) }
+    }
+}
+
+impl Drop for WriteWindow<'_> {
+    fn drop(&mut self) {
+        self.arena.shrink(self.place, self.len, 0);
+    }
+}
+
+impl DynArena {
+    /// The number of bytes that have not been acquired yet, leaving out the
+    /// critical pool set with [`DynArena::set_critical_reserve`], and the room for
+    /// the canary after them with the `canaries` feature.
+    pub fn spare_capacity(&self) -> usize {
+        self.best_effort_capacity()
+            .saturating_sub(self.next_free_store_spot.load(Ordering::Acquire))
+            .saturating_sub(CANARY)
+    }
+
+    /// A view of the bytes that have not been acquired yet, e.g. for staging data
+    /// before deciding whether to keep it with [`DynArena::begin_write`].
+    ///
+    /// # Safety
+    /// Nothing may be acquired from the arena while the view is in use,
+    /// since acquisitions are placed in the same bytes.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn spare_bytes(&self) -> &mut [MaybeUninit<u8>] {
+        #[cfg(any(feature = "asan", feature = "valgrind"))]
+        self.start_annotations();
+
+        let used = self.next_free_store_spot.load(Ordering::Acquire);
+        let start = unsafe { self.store().cast::<u8>().add(used) };
+        let len = self
+            .best_effort_capacity()
+            .saturating_sub(used)
+            .saturating_sub(CANARY);
+        annotate::expose(start, len);
+        unsafe { slice::from_raw_parts_mut(start.cast(), len) }
+    }
+
+    /// Hand out all of the bytes that have not been acquired yet, zeroed, e.g. to give
+    /// what is left of a static arena once initialization is done to another allocator.
+    /// The arena is full from then on, its critical pool included; without one,
+    /// [`DynArena::spare_capacity`] tells how much this will hand out.
+    // Resetting the arena, which would hand the bytes out again, takes a mutable
+    // borrow, which a `'static` shared borrow rules out for good.
+    #[allow(clippy::mut_from_ref)]
+    pub fn split_off_tail(&'static self) -> &'static mut [u8] {
+        #[cfg(any(feature = "asan", feature = "valgrind"))]
+        self.start_annotations();
+
+        let capacity = self.capacity();
+        // With the `canaries` feature the tail ends in a canary, and is empty if
+        // there is no room left for one.
+        let place = self
+            .next_free_store_spot
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |cursor| {
+                (cursor + CANARY <= capacity).then_some(capacity)
+            })
+            .unwrap_or(capacity);
+        let store = self.store().cast::<u8>();
+        let tail = unsafe { store.add(place) };
+        annotate::reserve(store, tail, capacity - place);
+        let len = (capacity - place).saturating_sub(CANARY);
+        #[cfg(feature = "canaries")]
+        if place != capacity {
+            self.write_canary(place + len, place);
+        }
+        unsafe { tail.write_bytes(0, len) };
+        unsafe { slice::from_raw_parts_mut(tail, len) }
+    }
+
+    /// Reserve room for up to `max` bytes to be written and then committed.
+    pub fn begin_write(&self, max: usize) -> Option<WriteWindow<'_>> {
+        self.begin_write_aligned(max, 1)
+    }
+
+    /// Like [`DynArena::begin_write`], but with the room starting at a multiple of `align`.
+    pub(crate) fn begin_write_aligned(&self, max: usize, align: usize) -> Option<WriteWindow<'_>> {
+        let place = self.reserve(max, align)?;
+        Some(WriteWindow {
+            arena: self,
+            place,
+            len: max,
+        })
+    }
+
+    /// acquire the bytes written by an encoder into room for up to `max` bytes,
+    /// e.g. a postcard, protobuf or DNS encoder given a buffer to fill.
+    /// `f` returns how many bytes it wrote; the rest of the room is handed back,
+    /// unless something else was acquired in the meantime.
+    pub fn encode_with(&self, max: usize, f: impl FnOnce(&mut [u8]) -> usize) -> Option<&[u8]> {
+        let mut window = self.begin_write(max)?;
+        let buf = window.buf();
+        buf.fill(MaybeUninit::new(0));
+        let buf = unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr().cast(), buf.len()) };
+        let len = f(buf);
+        Some(unsafe { window.commit(len) })
+    }
+
+    /// acquire a slice of up to `max` bytes filled by a single read from `r`.
+    /// Room for `max` bytes is reserved up front and whatever the read does not
+    /// use is handed back, unless something else was acquired in the meantime.
+    #[cfg(feature = "embedded-io")]
+    pub fn acquire_read<R: embedded_io::Read + ?Sized>(
+        &self,
+        r: &mut R,
+        max: usize,
+    ) -> Result<&[u8], ReadError<R::Error>> {
+        let mut window = self.begin_write(max).ok_or(ReadError::OutOfSpace)?;
+
+        // The store may hold the padding of values dropped by a reset, so it is
+        // zeroed before being handed to the reader as initialized bytes.
+        let buf = window.buf();
+        buf.fill(MaybeUninit::new(0));
+        let buf = unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr().cast(), buf.len()) };
+
+        let len = r.read(buf).map_err(ReadError::Read)?;
+        Ok(unsafe { window.commit(len) })
+    }
+}
@@ -0,0 +1,127 @@
+//! Acquisition from interrupt handlers.
+//!
+//! Acquiring from an arena is lock-free, but an interrupt handler can preempt
+//! an acquisition on the same core, and anything that waits for that acquisition
+//! to finish would wait forever. [`DynArena::acquire_isr`] never waits on another
+//! context and never panics; it fails instead.
+//!
+//! With RTIC, a `static` arena needs no resource at all, since every task can
+//! borrow it. To keep a hardware task to the interrupt-safe path, hand it an
+//! [`IsrArena`] as a local resource:
+//!
+//! ```ignore
+//! static ARENA: Arena<4096> = Arena::new();
+//!
+//! #[local]
+//! struct Local {
+//!     rx_arena: IsrArena<'static>,
+//! }
+//!
+//! #[init]
+//! fn init(cx: init::Context) -> (Shared, Local) {
+//!     (Shared {}, Local { rx_arena: ARENA.isr() })
+//! }
+//!
+//! #[task(binds = USART1, local = [rx_arena])]
+//! fn on_rx(cx: on_rx::Context) {
+//!     let frame = cx.local.rx_arena.acquire(read_frame());
+//! }
+//! ```
+//!
+//! Only [`DynArena::acquire_isr`] and [`DynArena::try_acquire_isr`] are meant for
+//! interrupt context. The other ways to acquire retry without bound: with the `asan`
+//! or `valgrind` features they spin until a preempted context has finished setting
+//! the arena up, which from a handler that preempted it is forever. Like
+//! `acquire_isr`, they also retry for as long as other contexts keep winning the
+//! race for the same bytes or drop queue entries, which only another core or a
+//! higher-priority handler acquiring in a tight loop can make happen.
+//! `try_acquire_isr` gives up after [`ISR_TRIES`] lost races instead, so the time
+//! it takes is bounded. Callbacks set with [`DynArena::on_usage_threshold`] run in
+//! whichever context crosses the threshold, interrupt handlers included.
+
+use core::mem::{align_of, size_of};
+
+use crate::{takes_drop_slot, DynArena, TypeInfo};
+
+/// The number of races with other contexts [`DynArena::try_acquire_isr`] tries
+/// before giving up, for each of the backing store and the drop queue.
+pub const ISR_TRIES: usize = 4;
+
+impl DynArena {
+    /// acquire a reference to a value of type T that is initialized with the given value,
+    /// from an interrupt handler or any other context that may have preempted an acquisition.
+    /// Hands the value back if the arena or its drop queue has no room for it, or (with the
+    /// `asan` or `valgrind` features) if the preempted context is still setting the arena up.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_isr<T>(&self, val: T) -> Result<&T, T> {
+        self.acquire_isr_tries(val, usize::MAX)
+    }
+
+    /// Like [`DynArena::acquire_isr`], but never retries more than [`ISR_TRIES`]
+    /// times, also handing the value back once other contexts have won that many
+    /// races for the arena, so it is bounded in time as well as non-blocking.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn try_acquire_isr<T>(&self, val: T) -> Result<&T, T> {
+        self.acquire_isr_tries(val, ISR_TRIES)
+    }
+
+    #[cfg_attr(feature = "record", track_caller)]
+    fn acquire_isr_tries<T>(&self, val: T, tries: usize) -> Result<&T, T> {
+        #[cfg(any(feature = "asan", feature = "valgrind"))]
+        if !self.try_start_annotations() {
+            return Err(val);
+        }
+        let place = self.reserve_within(
+            size_of::<T>(),
+            align_of::<T>(),
+            self.best_effort_capacity(),
+            tries,
+        );
+        #[cfg(feature = "record")]
+        self.record::<T>(place.is_some());
+        let Some(place) = place else {
+            return Err(val);
+        };
+        let ptr = unsafe { self.store().cast::<u8>().add(place).cast::<T>() };
+        unsafe { ptr.write(val) };
+        if takes_drop_slot::<T>() && !self.try_push_dropper(place, TypeInfo::of::<T>(), tries) {
+            let val = unsafe { ptr.read() };
+            self.shrink(place, size_of::<T>(), 0);
+            return Err(val);
+        }
+        Ok(unsafe { &*ptr })
+    }
+
+    /// A handle to the arena that can only acquire through [`DynArena::acquire_isr`].
+    pub fn isr(&self) -> IsrArena<'_> {
+        IsrArena { arena: self }
+    }
+}
+
+/// A handle to an arena for interrupt handlers, which only acquires in ways that
+/// are safe from interrupt context.
+#[derive(Clone, Copy)]
+pub struct IsrArena<'a> {
+    arena: &'a DynArena,
+}
+
+impl<'a> IsrArena<'a> {
+    /// acquire a reference to a value of type T that is initialized with the given value,
+    /// handing the value back if that can not be done right away.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire<T>(&self, val: T) -> Result<&'a T, T> {
+        self.arena.acquire_isr(val)
+    }
+
+    /// Like [`IsrArena::acquire`], but bounded in time through [`DynArena::try_acquire_isr`].
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn try_acquire<T>(&self, val: T) -> Result<&'a T, T> {
+        self.arena.try_acquire_isr(val)
+    }
+
+    /// acquire a reference to a value of type T that is initialized with it's default value.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_default<T: Default>(&self) -> Option<&'a T> {
+        self.arena.acquire_isr(T::default()).ok()
+    }
+}
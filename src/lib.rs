@@ -25,7 +25,6 @@
 //! ```
 //! use arena_alloc::{Arena, Init};
 //! use std::cell::Cell;
-//! use std::ptr;
 //! use std::mem::MaybeUninit;
 //!     
 //! static ARENA: Arena<1000> = Arena::new();
@@ -44,19 +43,22 @@
 //! impl<'b, T> Init for CllNode<'b, T> {
 //!     type InitArg = T;
 //!     fn init(me: &mut MaybeUninit<Self>, arg: T) {
+//!         // Written field by field through the pointer the node refers to itself by,
+//!         // as writing the whole node through `me` would invalidate that reference.
+//!         let this = me.as_mut_ptr();
 //!         unsafe {
-//!             me.write(CllNode {
-//!                 data: arg,
-//!                 next: Cell::new(ptr::from_ref(me).cast::<Self>().as_ref().unwrap()),
-//!             });
+//!             (&raw mut (*this).data).write(arg);
+//!             (&raw mut (*this).next).write(Cell::new(&*this));
 //!         }
 //!     }
 //! }
 //! 
 //! fn main() {
-//!     let n0 = ARENA.acquire_init_default::<CllNode<usize>>().unwrap();
-//!     let n1 = ARENA.acquire_init::<CllNode<_>>(1).unwrap();
-//!     let n2 = ARENA.acquire_init::<CllNode<_>>(2).unwrap();
+//!     // Each node is reached through the reference it holds to itself, which keeps
+//!     // the links valid under Stacked Borrows; see `DynArena::acquire_init`.
+//!     let n0 = ARENA.acquire_init_default::<CllNode<usize>>().unwrap().next.get();
+//!     let n1 = ARENA.acquire_init::<CllNode<_>>(1).unwrap().next.get();
+//!     let n2 = ARENA.acquire_init::<CllNode<_>>(2).unwrap().next.get();
 //! 
 //!     n0.cons(n1);
 //!     n1.cons(n2);
@@ -68,29 +70,555 @@
 //! ```
 //!
 
-use core::{cell::UnsafeCell, mem::MaybeUninit, ptr, sync::atomic::{AtomicUsize, Ordering}, usize};
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::{cell::UnsafeCell, mem::{ManuallyDrop, MaybeUninit}, ops::{Deref, DerefMut}, ptr};
+use atomic::{AtomicUsize, Ordering};
+pub use alloc_trait::ArenaAlloc;
+pub use any::TypeRegistry;
+pub use avl::{AvlTree, TreeLink, TreeNode};
+#[cfg(feature = "derive")]
+pub use arena_alloc_derive::CloneIn;
+pub use arena_mut::{ArenaMut, Handle};
+pub use arena_set::ArenaSet;
+pub use branded::{Branded, BrandedRef};
+pub use btree::ArenaBTreeMap;
+pub use bytes::ByteArena;
+#[cfg(feature = "canaries")]
+use canary::CANARY;
+pub use child::DropOrder;
+pub use clone_in::CloneIn;
+pub use collect::{Chunked, Chunks};
+pub use const_arena::ConstArena;
+pub use cow::{ArenaCow, ToOwnedIn};
+pub use counted::{CountedArena, CountedRef, Stamped};
+#[cfg(feature = "serde")]
+pub use de::{ArenaSeed, DeserializeIn};
+pub use dedup::{DedupArena, InternStats, StrTable};
+pub use drop_panic::DropPanic;
+use drop_panic::DropPass;
+pub use error::{ArenaError, StrWriter};
+pub use event::{EventBus, HandlerId};
+pub use frame::FrameArena;
+pub use gap::GapBuffer;
+pub use global::{arena_alloc, arena_alloc_str};
+#[cfg(feature = "guard-pages")]
+pub use guarded::GuardedArena;
 pub use init::Init;
+#[cfg(feature = "embedded-io")]
+pub use io::ReadError;
+pub use io::WriteWindow;
+pub use isr::{IsrArena, ISR_TRIES};
+#[cfg(feature = "lifetimes")]
+pub use lifetimes::{Lifetimes, AGE_BUCKETS};
+#[cfg(feature = "std")]
+pub use locked::LockedArena;
+pub use mailbox::{Mailbox, Receiver, SendError, Sender};
+pub use matrix::Matrix;
+pub use offset::Offset;
+pub use packet::PacketBuf;
+pub use parse::{ParseError, ParseIn};
+pub use per_cpu::PerCpu;
+pub use queue::Queue;
+pub use rc::{ArenaRc, ArenaWeak};
+#[cfg(feature = "record")]
+pub use record::{Record, RecordSink, Recorder};
+pub use region::RegionArena;
+pub use registry::{registered, report_all, REGISTRY_SLOTS};
+pub use ring_log::RingLog;
+pub use scope::Scope;
+pub use scratch::{Scratch, ScratchRegion};
+#[cfg(feature = "alloc-info")]
+pub use snapshot::{Change, Snapshot, SnapshotDiff, Tally};
+pub use stats::Stats;
+pub use tailed::Tailed;
+#[cfg(feature = "std")]
+pub use thread_local::with_thread_arena;
+pub use trie::Trie;
+use watch::{Watch, WATCHES};
+pub use window::DropList;
 
+mod aligned;
+mod alloc_trait;
+mod any;
+mod annotate;
+#[cfg(feature = "rkyv")]
+mod archive;
+mod arena_mut;
+mod arena_set;
+mod atomic;
+mod avl;
+mod branded;
+mod btree;
+mod bytes;
+#[cfg(feature = "canaries")]
+mod canary;
+mod child;
+mod clone_in;
+mod collect;
+mod const_arena;
+mod counted;
+mod cow;
+mod critical;
+#[cfg(feature = "serde")]
+mod de;
+mod dedup;
+mod drop_panic;
+mod emplace;
+mod error;
+mod event;
+#[cfg(feature = "fail-inject")]
+mod fail;
+mod fill;
+mod finalize;
+mod frame;
+#[cfg(feature = "zerocopy")]
+mod from_bytes;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+mod gap;
+mod global;
+#[cfg(feature = "guard-pages")]
+mod guarded;
+#[cfg(feature = "alloc-info")]
+mod info;
 mod init;
+mod io;
+mod isr;
+#[cfg(feature = "lifetimes")]
+mod lifetimes;
+#[cfg(feature = "std")]
+mod locked;
+mod mailbox;
+mod make_static;
+mod matrix;
+mod offset;
+mod packet;
+mod pages;
+mod parse;
+mod per_cpu;
+#[cfg(feature = "bytemuck")]
+mod pod;
+#[cfg(feature = "std")]
+mod prefault;
+mod queue;
+mod rc;
+#[cfg(feature = "record")]
+mod record;
+mod region;
+mod registry;
+mod ring_log;
+mod scope;
+mod scratch;
+#[cfg(feature = "zeroize")]
+mod secret;
+mod slice;
+#[cfg(feature = "alloc-info")]
+mod snapshot;
+mod stats;
+#[cfg(feature = "std")]
+mod sys;
+mod tailed;
+mod template;
+#[cfg(feature = "std")]
+mod thread_local;
+mod trie;
+mod watch;
+mod window;
+
+/// The backing store of an arena, aligned so that offsets that are aligned for
+/// a type stay aligned if the arena is moved.
+// Held as MaybeUninit bytes, whose copies keep the provenance of any pointers
+// stored in them when the arena is moved, where copies of u8s would drop it.
+#[repr(C, align(16))]
+struct MemSlice<const SIZE: usize>([MaybeUninit<u8>; SIZE]);
+
+impl<const SIZE: usize> MemSlice<SIZE> {
+    /// A zeroed backing store.
+    const fn new() -> Self {
+        MemSlice([MaybeUninit::new(0); SIZE])
+    }
+}
+
+/// The bytes reserved after every value for a canary, with the `canaries` feature.
+#[cfg(not(feature = "canaries"))]
+const CANARY: usize = 0;
+
+/// Find the (start, end) offsets for a value of the given size and alignment
+/// placed at or after `cursor` in a store of `capacity` bytes beginning at `base`.
+fn align_place(base: usize, cursor: usize, size: usize, align: usize, capacity: usize) -> Option<(usize, usize)> {
+    let start = base.checked_add(cursor)?.checked_next_multiple_of(align)? - base;
+    let end = start.checked_add(size)?;
+    (end <= capacity).then_some((start, end))
+}
+
+/// What the drop queue needs to know about a type, kept in one static per type
+/// so that drop queue entries stay two words long.
+struct TypeInfo {
+    drop_func: fn(*mut u8),
+    /// Whether `drop_func` does anything, which a reset can skip it if not.
+    needs_drop: bool,
+    /// Whether the value was acquired as the child of another, see `acquire_child_of`.
+    child: bool,
+    #[cfg(feature = "alloc-info")]
+    size: fn(*mut u8) -> usize,
+    #[cfg(feature = "alloc-info")]
+    type_name: fn() -> &'static str,
+    /// The alignment the value was placed with.
+    #[cfg(feature = "alloc-info")]
+    align: usize,
+    /// The number of bytes between the place of the value and its contents.
+    #[cfg(feature = "alloc-info")]
+    header: usize,
+    /// The TypeId of a value acquired with `acquire_tagged`, which `get` checks.
+    #[cfg(feature = "type-tags")]
+    type_id: Option<fn() -> core::any::TypeId>,
+}
+
+impl TypeInfo {
+    /// The info for type T.
+    const fn of<T>() -> &'static TypeInfo {
+        const {
+            &TypeInfo {
+                drop_func: |ptr: *mut u8| unsafe {
+                    ptr.cast::<T>().drop_in_place();
+                },
+                needs_drop: core::mem::needs_drop::<T>(),
+                child: false,
+                #[cfg(feature = "alloc-info")]
+                size: |_| core::mem::size_of::<T>(),
+                #[cfg(feature = "alloc-info")]
+                type_name: core::any::type_name::<T>,
+                #[cfg(feature = "alloc-info")]
+                align: core::mem::align_of::<T>(),
+                #[cfg(feature = "alloc-info")]
+                header: 0,
+                #[cfg(feature = "type-tags")]
+                type_id: None,
+            }
+        }
+    }
+
+    /// The info for a slice of T, whose length is kept in a header before its elements.
+    fn of_slice<T>() -> &'static TypeInfo {
+        const {
+            &TypeInfo {
+                drop_func: |ptr: *mut u8| unsafe {
+                    let (elements, len) = slice_parts::<T>(ptr);
+                    ptr::slice_from_raw_parts_mut(elements, len).drop_in_place();
+                },
+                needs_drop: core::mem::needs_drop::<T>(),
+                child: false,
+                #[cfg(feature = "alloc-info")]
+                size: |ptr| unsafe { slice_parts::<T>(ptr).1 * core::mem::size_of::<T>() },
+                #[cfg(feature = "alloc-info")]
+                type_name: core::any::type_name::<[T]>,
+                #[cfg(feature = "alloc-info")]
+                align: if core::mem::align_of::<T>() > core::mem::align_of::<usize>() {
+                    core::mem::align_of::<T>()
+                } else {
+                    core::mem::align_of::<usize>()
+                },
+                #[cfg(feature = "alloc-info")]
+                header: slice_header::<T>(),
+                #[cfg(feature = "type-tags")]
+                type_id: None,
+            }
+        }
+    }
+}
+
+/// Whether a value of type T takes an entry in the drop queue. Zero-sized values
+/// with nothing to drop take none, so that any number of them can be acquired.
+const fn takes_drop_slot<T>() -> bool {
+    core::mem::size_of::<T>() != 0 || core::mem::needs_drop::<T>()
+}
+
+/// The number of bytes between the start of the length header of a slice of T and its elements.
+const fn slice_header<T>() -> usize {
+    core::mem::size_of::<usize>().next_multiple_of(core::mem::align_of::<T>())
+}
+
+/// The elements and length of a slice of T whose length header is at `ptr`.
+///
+/// # Safety
+/// `ptr` must point to the length header of a slice of T.
+unsafe fn slice_parts<T>(ptr: *mut u8) -> (*mut T, usize) {
+    unsafe { (ptr.add(slice_header::<T>()).cast(), ptr.cast::<usize>().read()) }
+}
+
+/// Hands a reservation back to its arena, if nothing has been reserved after it,
+/// unless it is kept, e.g. when the constructor of the value meant for it panics.
+struct Reservation<'a> {
+    arena: &'a DynArena,
+    place: usize,
+    len: usize,
+}
+
+impl<'a> Reservation<'a> {
+    /// Guard the `len` bytes reserved at `place` in `arena`.
+    fn new(arena: &'a DynArena, place: usize, len: usize) -> Self {
+        Reservation { arena, place, len }
+    }
+
+    /// Keep the reservation, now that its value has been written.
+    fn keep(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        self.arena.shrink(self.place, self.len, 0);
+    }
+}
+
+/// Entries claimed in the drop queue of an arena, before the values they are for
+/// are written, so that a full queue is found while a value can still be handed
+/// back. Entries that are not filled, e.g. when the constructor of the value meant
+/// for one panics, are handed back, or left empty if later entries have been claimed.
+struct DropClaim<'a> {
+    arena: &'a DynArena,
+    slots: core::ops::Range<usize>,
+}
 
-type MemSlice<const SIZE: usize> = [u8; SIZE];
+impl DropClaim<'_> {
+    /// Fill the next entry with a dropper described by `info` for the value at `place`.
+    /// Does nothing if no entry is left, e.g. for a value that takes none.
+    #[cfg_attr(feature = "record", track_caller)]
+    fn fill(&mut self, place: usize, info: &'static TypeInfo) {
+        if let Some(index) = self.slots.next() {
+            self.arena.write_dropper(index, place, info);
+        }
+    }
+}
+
+impl Drop for DropClaim<'_> {
+    fn drop(&mut self) {
+        if self.slots.is_empty() {
+            return;
+        }
+        let arena = self.arena;
+        if arena
+            .next_free_drop_spot
+            .compare_exchange(
+                self.slots.end,
+                self.slots.start,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+        {
+            return;
+        }
+        for index in self.slots.clone() {
+            unsafe {
+                arena
+                    .storage
+                    .drop_queue()
+                    .cast::<DropSlot>()
+                    .add(index)
+                    .write(DropSlot::EMPTY)
+            };
+            #[cfg(feature = "alloc-info")]
+            arena.published_drop_spots.fetch_add(1, Ordering::Release);
+        }
+    }
+}
 
 #[derive(Clone, Copy)]
-struct Dropper<const SIZE: usize> {
+struct Dropper {
     place: usize,
-    drop_func: fn(*mut MemSlice<SIZE>),
+    info: &'static TypeInfo,
+    /// Where the value was acquired.
+    #[cfg(feature = "call-sites")]
+    location: &'static core::panic::Location<'static>,
+    /// When the value was acquired.
+    #[cfg(feature = "lifetimes")]
+    acquired: u64,
 }
 
-/// A fixed size arena that can be used to allocate memory for arbitrary types.
-pub struct Arena<const SIZE: usize> {
+/// An entry in the drop queue of an arena, which holds the destructor of one value.
+#[derive(Clone, Copy)]
+pub struct DropSlot(Option<Dropper>);
+
+impl DropSlot {
+    /// A slot that holds no destructor.
+    pub const EMPTY: Self = DropSlot(None);
+
+    /// Whether the slot holds the destructor of a child of another value.
+    fn is_child(&self) -> bool {
+        self.0.is_some_and(|dropper| dropper.info.child)
+    }
+}
+
+impl Default for DropSlot {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+mod sealed {
+    /// Where an arena keeps its values and the destructors of those values.
+    pub trait Storage {
+        fn store(&self) -> *mut [u8];
+        fn drop_queue(&self) -> *mut [crate::DropSlot];
+    }
+
+    /// An unsigned integer type that offsets into an arena can be kept in.
+    pub trait Offset: Copy + Eq + core::hash::Hash + core::fmt::Debug {
+        /// The largest offset the type can hold.
+        const MAX: usize;
+
+        /// `n`, which must be at most `MAX`.
+        fn from_usize(n: usize) -> Self;
+
+        fn to_usize(self) -> usize;
+    }
+
+    macro_rules! offset {
+        ($($ty:ty),*) => {$(
+            impl Offset for $ty {
+                const MAX: usize = if <$ty>::BITS < usize::BITS {
+                    <$ty>::MAX as usize
+                } else {
+                    usize::MAX
+                };
+
+                fn from_usize(n: usize) -> Self {
+                    n as $ty
+                }
+
+                fn to_usize(self) -> usize {
+                    self as usize
+                }
+            }
+        )*};
+    }
+
+    offset!(u16, u32, usize);
+}
+
+use sealed::Storage;
+
+/// The storage of an [`Arena`], held inline.
+// The backing store is laid out last so that an overrun of it runs off the end
+// of the arena (into a guard page, for a `GuardedArena`).
+#[repr(C)]
+struct Inline<const SIZE: usize> {
+    drop_queue: UnsafeCell<[DropSlot; SIZE]>,
     backing_store: UnsafeCell<MemSlice<SIZE>>,
+}
+
+impl<const SIZE: usize> Storage for Inline<SIZE> {
+    fn store(&self) -> *mut [u8] {
+        ptr::slice_from_raw_parts_mut(self.backing_store.get().cast(), SIZE)
+    }
+
+    fn drop_queue(&self) -> *mut [DropSlot] {
+        ptr::slice_from_raw_parts_mut(self.drop_queue.get().cast(), SIZE)
+    }
+}
+
+/// An arena whose capacity is not part of its type, so that code can take any
+/// arena as a `&DynArena` without being generic over its size.
+///
+/// Every [`Arena`] and [`RegionArena`] derefs to one.
+#[repr(C)]
+pub struct DynArena<S: ?Sized + Storage = dyn Storage> {
     next_free_store_spot: AtomicUsize,
-    drop_queue: UnsafeCell<[Option<Dropper<SIZE>>; SIZE]>,
     next_free_drop_spot: AtomicUsize,
+    /// The number of bytes skipped to align reservations since the last reset.
+    padding: AtomicUsize,
+    /// The largest alignment any reservation has been made with.
+    max_align: AtomicUsize,
+    /// The number of reservations that found no room, their total size and the
+    /// largest of them.
+    failures: AtomicUsize,
+    failed_bytes: AtomicUsize,
+    largest_failure: AtomicUsize,
+    /// The number of bytes at the end of the store that only critical acquisitions may use.
+    critical: AtomicUsize,
+    /// The usage thresholds set with `on_usage_threshold`.
+    watches: [Watch; WATCHES],
+    /// A bit for every watch whose callback has yet to be called.
+    armed_watches: atomic::AtomicU8,
+    /// Whether children are dropped after their parents rather than before.
+    parents_first: atomic::AtomicBool,
+    /// One past the offset of the last finalizer registered with `on_drop`, or zero.
+    finalizers: AtomicUsize,
+    /// Whether a destructor that panics while the arena drops its values aborts.
+    abort_on_drop_panic: atomic::AtomicBool,
+    /// Whether a value that needs dropping has been acquired since the last reset.
+    must_drop: atomic::AtomicBool,
+    /// One more than the byte set with `set_fill`, or zero.
+    #[cfg(debug_assertions)]
+    fill: AtomicUsize,
+    #[cfg(feature = "alloc-info")]
+    published_drop_spots: AtomicUsize,
+    #[cfg(feature = "lifetimes")]
+    drop_ages: lifetimes::DropAges,
+    generation: AtomicUsize,
+    #[cfg(any(feature = "asan", feature = "valgrind"))]
+    annotations: atomic::AtomicU8,
+    #[cfg(feature = "record")]
+    recorder: Option<&'static dyn RecordSink>,
+    /// The number of allocations until one is made to fail, or zero.
+    #[cfg(feature = "fail-inject")]
+    fail_countdown: AtomicUsize,
+    /// A `fn(usize) -> bool` that picks the sizes of allocations to fail, or null.
+    #[cfg(feature = "fail-inject")]
+    fail_if: atomic::AtomicPtr<()>,
+    storage: S,
 }
 
-unsafe impl<const SIZE: usize> Sync for Arena<SIZE> {}
-unsafe impl<const SIZE: usize> Send for Arena<SIZE> {}
+unsafe impl<S: ?Sized + Storage> Sync for DynArena<S> {}
+unsafe impl<S: ?Sized + Storage> Send for DynArena<S> {}
+
+impl<S: Storage> DynArena<S> {
+    /// Create a new arena that keeps its values in `storage`.
+    const fn with_storage(storage: S) -> Self {
+        DynArena {
+            next_free_store_spot: AtomicUsize::new(0),
+            next_free_drop_spot: AtomicUsize::new(0),
+            padding: AtomicUsize::new(0),
+            max_align: AtomicUsize::new(1),
+            failures: AtomicUsize::new(0),
+            failed_bytes: AtomicUsize::new(0),
+            largest_failure: AtomicUsize::new(0),
+            critical: AtomicUsize::new(0),
+            watches: [const { Watch::free() }; WATCHES],
+            armed_watches: atomic::AtomicU8::new(0),
+            parents_first: atomic::AtomicBool::new(false),
+            finalizers: AtomicUsize::new(0),
+            abort_on_drop_panic: atomic::AtomicBool::new(false),
+            must_drop: atomic::AtomicBool::new(false),
+            #[cfg(debug_assertions)]
+            fill: AtomicUsize::new(0),
+            #[cfg(feature = "alloc-info")]
+            published_drop_spots: AtomicUsize::new(0),
+            #[cfg(feature = "lifetimes")]
+            drop_ages: std::sync::Mutex::new(std::vec::Vec::new()),
+            generation: AtomicUsize::new(0),
+            #[cfg(any(feature = "asan", feature = "valgrind"))]
+            annotations: atomic::AtomicU8::new(0),
+            #[cfg(feature = "record")]
+            recorder: None,
+            #[cfg(feature = "fail-inject")]
+            fail_countdown: AtomicUsize::new(0),
+            #[cfg(feature = "fail-inject")]
+            fail_if: atomic::AtomicPtr::new(ptr::null_mut()),
+            storage,
+        }
+    }
+}
+
+/// A fixed size arena that can be used to allocate memory for arbitrary types.
+///
+/// Derefs to a [`DynArena`], which has all of its methods.
+#[repr(transparent)]
+pub struct Arena<const SIZE: usize>(DynArena<Inline<SIZE>>);
 
 impl<const SIZE: usize> Default for Arena<SIZE> {
     fn default() -> Self {
@@ -98,32 +626,291 @@ impl<const SIZE: usize> Default for Arena<SIZE> {
     }
 }
 
-impl<'a, const SIZE: usize> Arena<SIZE> {
+impl<const SIZE: usize> Arena<SIZE> {
     /// Create a new arena with a fixed size buffer of SIZE bytes.
     #[must_use]
     pub const fn new() -> Self {
-        Arena {
-            backing_store: UnsafeCell::new([0; SIZE]),
+        // Built in place with a constant storage so that debug builds do not
+        // need stack space for temporary copies of a large storage.
+        Arena(DynArena {
             next_free_store_spot: AtomicUsize::new(0),
-            drop_queue: UnsafeCell::new([None; SIZE]),
             next_free_drop_spot: AtomicUsize::new(0),
+            padding: AtomicUsize::new(0),
+            max_align: AtomicUsize::new(1),
+            failures: AtomicUsize::new(0),
+            failed_bytes: AtomicUsize::new(0),
+            largest_failure: AtomicUsize::new(0),
+            critical: AtomicUsize::new(0),
+            watches: [const { Watch::free() }; WATCHES],
+            armed_watches: atomic::AtomicU8::new(0),
+            parents_first: atomic::AtomicBool::new(false),
+            finalizers: AtomicUsize::new(0),
+            abort_on_drop_panic: atomic::AtomicBool::new(false),
+            must_drop: atomic::AtomicBool::new(false),
+            #[cfg(debug_assertions)]
+            fill: AtomicUsize::new(0),
+            #[cfg(feature = "alloc-info")]
+            published_drop_spots: AtomicUsize::new(0),
+            #[cfg(feature = "lifetimes")]
+            drop_ages: std::sync::Mutex::new(std::vec::Vec::new()),
+            generation: AtomicUsize::new(0),
+            #[cfg(any(feature = "asan", feature = "valgrind"))]
+            annotations: atomic::AtomicU8::new(0),
+            #[cfg(feature = "record")]
+            recorder: None,
+            #[cfg(feature = "fail-inject")]
+            fail_countdown: AtomicUsize::new(0),
+            #[cfg(feature = "fail-inject")]
+            fail_if: atomic::AtomicPtr::new(ptr::null_mut()),
+            storage: const {
+                Inline {
+                    drop_queue: UnsafeCell::new([DropSlot::EMPTY; SIZE]),
+                    backing_store: UnsafeCell::new(MemSlice::new()),
+                }
+            },
+        })
+    }
+}
+
+impl<const SIZE: usize> Deref for Arena<SIZE> {
+    type Target = DynArena;
+
+    fn deref(&self) -> &DynArena {
+        &self.0
+    }
+}
+
+impl<const SIZE: usize> DerefMut for Arena<SIZE> {
+    fn deref_mut(&mut self) -> &mut DynArena {
+        &mut self.0
+    }
+}
+
+impl<const SIZE: usize> Drop for Arena<SIZE> {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+impl<'a> DynArena {
+    /// The number of bytes the arena can hold.
+    pub fn capacity(&self) -> usize {
+        self.store().len()
+    }
+
+    /// The backing store.
+    fn store(&self) -> *mut [u8] {
+        self.storage.store()
+    }
+
+    /// Mark the whole backing store as unallocated for the sanitizers, once.
+    #[cfg(any(feature = "asan", feature = "valgrind"))]
+    fn start_annotations(&self) {
+        while !self.try_start_annotations() {
+            core::hint::spin_loop();
         }
     }
 
-    /// Get a pointer to a place in the backing store where a value of type T can be placed.
-    fn get_ptr_place<T>(&'a self) -> Option<(usize, &mut MaybeUninit<T>)> {
-        let place = self.next_free_store_spot.fetch_add(
-            core::mem::size_of::<T>(),
-            Ordering::Release,
-        );
-        if place + core::mem::size_of::<T>() > SIZE {
+    /// Like `start_annotations`, but returns false instead of waiting if another
+    /// context is marking the backing store right now.
+    #[cfg(any(feature = "asan", feature = "valgrind"))]
+    fn try_start_annotations(&self) -> bool {
+        const STARTING: u8 = 1;
+        const STARTED: u8 = 2;
+        if self.annotations.load(Ordering::Acquire) == STARTED {
+            return true;
+        }
+        match self
+            .annotations
+            .compare_exchange(0, STARTING, Ordering::Acquire, Ordering::Acquire)
+        {
+            Ok(_) => {
+                annotate::start(self.store().cast(), self.capacity());
+                self.annotations.store(STARTED, Ordering::Release);
+                true
+            }
+            Err(state) => state == STARTED,
+        }
+    }
+
+    /// Whether the sanitizers have been told about the backing store.
+    fn annotations_started(&self) -> bool {
+        #[cfg(any(feature = "asan", feature = "valgrind"))]
+        return self.annotations.load(Ordering::Acquire) != 0;
+        #[cfg(not(any(feature = "asan", feature = "valgrind")))]
+        return false;
+    }
+
+    /// Reserve `size` bytes aligned to `align` in the backing store, returning their offset.
+    fn reserve(&self, size: usize, align: usize) -> Option<usize> {
+        self.reserve_within(size, align, self.best_effort_capacity(), usize::MAX)
+    }
+
+    /// Reserve `size` bytes aligned to `align` in the first `capacity` bytes of the
+    /// backing store, returning their offset. Gives up once `tries` attempts have
+    /// been lost to other contexts moving the cursor first.
+    fn reserve_within(
+        &self,
+        size: usize,
+        align: usize,
+        capacity: usize,
+        tries: usize,
+    ) -> Option<usize> {
+        let place = self.claim_within(size, align, capacity, tries);
+        if place.is_none() {
+            self.count_failure(size);
+        }
+        place
+    }
+
+    /// [`DynArena::reserve_within`], without counting a failure.
+    fn claim_within(
+        &self,
+        size: usize,
+        align: usize,
+        capacity: usize,
+        mut tries: usize,
+    ) -> Option<usize> {
+        #[cfg(any(feature = "asan", feature = "valgrind"))]
+        self.start_annotations();
+
+        #[cfg(feature = "fail-inject")]
+        if self.injected_failure(size) {
+            return None;
+        }
+
+        let store = self.store().cast::<u8>();
+        if size == 0 {
+            // Zero-sized values take no space, so they can go anywhere aligned
+            // without moving the cursor.
+            return align_place(store.addr(), 0, 0, align, capacity).map(|(place, _)| place);
+        }
+        // Room for a canary after the value, with the `canaries` feature.
+        let reserved = size.checked_add(CANARY)?;
+        // A strong compare-exchange, so that only cursor moves by other contexts,
+        // and not spurious failures on LL/SC targets, count against `tries`.
+        let mut cursor = self.next_free_store_spot.load(Ordering::Acquire);
+        let (place, end) = loop {
+            tries = tries.checked_sub(1)?;
+            let (place, end) = align_place(store.addr(), cursor, reserved, align, capacity)?;
+            match self.next_free_store_spot.compare_exchange(
+                cursor,
+                end,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break (place, end),
+                Err(moved) => cursor = moved,
+            }
+        };
+        if place != cursor {
+            self.padding.fetch_add(place - cursor, Ordering::Relaxed);
+        }
+        if align > self.max_align.load(Ordering::Relaxed) {
+            self.max_align.fetch_max(align, Ordering::Relaxed);
+        }
+
+        annotate::reserve(store, unsafe { store.add(place) }, reserved);
+        #[cfg(debug_assertions)]
+        self.fill_reserved(place, size);
+        #[cfg(feature = "canaries")]
+        self.write_canary(place + size, cursor);
+        self.check_watches(end);
+
+        Some(place)
+    }
+
+    /// Reserve `len` bytes with no alignment in the backing store, returning their offset.
+    fn reserve_bytes(&self, len: usize) -> Option<usize> {
+        let place = self.claim_bytes(len);
+        if place.is_none() {
+            self.count_failure(len);
+        }
+        place
+    }
+
+    /// [`DynArena::reserve_bytes`], without counting a failure.
+    fn claim_bytes(&self, len: usize) -> Option<usize> {
+        #[cfg(any(feature = "asan", feature = "valgrind"))]
+        self.start_annotations();
+
+        #[cfg(feature = "fail-inject")]
+        if self.injected_failure(len) {
             return None;
         }
 
+        let capacity = self.best_effort_capacity();
+        if len == 0 {
+            // Empty buffers take no room, and so need no canary.
+            let cursor = self.next_free_store_spot.load(Ordering::Acquire);
+            return (cursor <= capacity).then_some(cursor);
+        }
+        let reserved = len.checked_add(CANARY)?;
+        let place = self
+            .next_free_store_spot
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |cursor| {
+                cursor.checked_add(reserved).filter(|end| *end <= capacity)
+            })
+            .ok()?;
+
+        let store = self.store().cast::<u8>();
+        annotate::reserve(store, unsafe { store.add(place) }, reserved);
+        #[cfg(debug_assertions)]
+        self.fill_reserved(place, len);
+        #[cfg(feature = "canaries")]
+        self.write_canary(place + len, place);
+        self.check_watches(place + reserved);
+
+        Some(place)
+    }
+
+    /// Shrink the reservation of `len` bytes at `place` to its first `keep` bytes,
+    /// handing the rest back if nothing has been reserved after it.
+    fn shrink(&self, place: usize, len: usize, keep: usize) {
+        // A canary moves to the end of what is kept, or is handed back with the
+        // padding before the reservation if nothing is.
+        let kept = if keep == 0 { 0 } else { keep + CANARY };
+        #[cfg(feature = "canaries")]
+        let prev = match place.checked_add(len + CANARY) {
+            // Zero-sized values take no room, and have no canary to read.
+            Some(end) if len != 0 && end <= self.capacity() => self.canary_link(place + len),
+            _ => return,
+        };
+        #[cfg(feature = "canaries")]
+        let cursor = if keep == 0 { prev } else { place + kept };
+        #[cfg(not(feature = "canaries"))]
+        let cursor = place + kept;
+        let released = self
+            .next_free_store_spot
+            .compare_exchange(place + len + CANARY, cursor, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok();
+        if released {
+            #[cfg(feature = "canaries")]
+            if keep == 0 {
+                self.padding.fetch_sub(place - prev, Ordering::Relaxed);
+            } else {
+                self.write_canary(place + keep, prev);
+            }
+            let store = self.store().cast::<u8>();
+            annotate::shrink(store, unsafe { store.add(place) }, len + CANARY, kept);
+        }
+    }
+
+    /// Get a pointer to a place in the backing store where a value of type T can be placed.
+    // Every call reserves a disjoint region of the backing store, so handing out
+    // a mutable reference to it from a shared borrow of the arena is sound.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "record", track_caller)]
+    fn get_ptr_place<T>(&'a self) -> Option<(usize, &'a mut MaybeUninit<T>)> {
+        let place = self.reserve(core::mem::size_of::<T>(), core::mem::align_of::<T>());
+        #[cfg(feature = "record")]
+        self.record::<T>(place.is_some());
+        let place = place?;
+
         let ptr = unsafe {
-            self.backing_store
-                .get()
-                .byte_add(place)
+            self.store()
+                .cast::<u8>()
+                .add(place)
                 .cast::<MaybeUninit<T>>()
                 .as_mut()
                 .unwrap()
@@ -132,31 +919,101 @@ impl<'a, const SIZE: usize> Arena<SIZE> {
         Some((place, ptr))
     }
 
-    /// Add a dropper function for type T at the given place to the drop queue.
-    fn add_to_drop_queue<T>(&'a self, place: usize) {
-        let dq = unsafe { self.drop_queue.get().as_mut() }.unwrap();
-        dq[self
-            .next_free_drop_spot
-            .fetch_add(1, Ordering::Relaxed)] = Some(Dropper {
-            place,
-            drop_func: |ptr: *mut MemSlice<SIZE>| unsafe {
-                ptr.cast::<T>().drop_in_place();
-            },
-        });
+    /// Claim an entry in the drop queue for a value of type T, if it takes one.
+    fn claim_drop_slot_for<T>(&'a self) -> Option<DropClaim<'a>> {
+        self.claim_drop_slots(usize::from(takes_drop_slot::<T>()))
+    }
+
+    /// Claim `count` entries in the drop queue, or none if it has too few left.
+    fn claim_drop_slots(&'a self, count: usize) -> Option<DropClaim<'a>> {
+        let len = self.storage.drop_queue().len();
+        let start = if count == 0 {
+            0
+        } else {
+            self.next_free_drop_spot
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |index| {
+                    index.checked_add(count).filter(|end| *end <= len)
+                })
+                .ok()?
+        };
+        Some(DropClaim {
+            arena: self,
+            slots: start..start + count,
+        })
+    }
+
+    /// Write a dropper described by `info` for the value at `place` into the drop queue,
+    /// returning false if the drop queue is full, or once `tries` attempts have been
+    /// lost to other contexts claiming an entry first.
+    #[cfg_attr(feature = "record", track_caller)]
+    fn try_push_dropper(&self, place: usize, info: &'static TypeInfo, mut tries: usize) -> bool {
+        let len = self.storage.drop_queue().len();
+        let mut index = self.next_free_drop_spot.load(Ordering::Relaxed);
+        loop {
+            if index >= len || tries == 0 {
+                return false;
+            }
+            tries -= 1;
+            match self.next_free_drop_spot.compare_exchange(
+                index,
+                index + 1,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(claimed) => index = claimed,
+            }
+        }
+        self.write_dropper(index, place, info);
+        true
+    }
+
+    /// Write a dropper into the drop queue entry at `index`, which has been claimed for it.
+    #[cfg_attr(feature = "record", track_caller)]
+    fn write_dropper(&self, index: usize, place: usize, info: &'static TypeInfo) {
+        // Checked first, so that the flag's cache line is only written once per reset.
+        if info.needs_drop && !self.must_drop.load(Ordering::Relaxed) {
+            self.must_drop.store(true, Ordering::Release);
+        }
+        let drop_queue = self.storage.drop_queue();
+        unsafe {
+            drop_queue
+                .cast::<DropSlot>()
+                .add(index)
+                .write(DropSlot(Some(Dropper {
+                    place,
+                    info,
+                    #[cfg(feature = "call-sites")]
+                    location: core::panic::Location::caller(),
+                    #[cfg(feature = "lifetimes")]
+                    acquired: lifetimes::now(),
+                })));
+        }
+        #[cfg(feature = "alloc-info")]
+        self.published_drop_spots.fetch_add(1, Ordering::Release);
     }
 
     /// acquire a reference to a value of type T that can be initialized with
     /// the Init trait, using the default value of the InitArg.
     /// This is useful for types that require initialization and the init arg is Default.
+    /// If `T::init` panics, the value is never dropped and its room is handed back.
+    ///
+    /// Under Stacked Borrows, as Miri checks by default, writing to the value through
+    /// the returned reference, e.g. to a `Cell`, invalidates the references the value
+    /// holds to itself. Reach it through one of those instead, if it is written to.
+    #[cfg_attr(feature = "record", track_caller)]
     pub fn acquire_init_default<T: Init>(&'a self) -> Option<&'a T>
     where
         T::InitArg: Default,
     {
+        let mut slot = self.claim_drop_slot_for::<T>()?;
         let (place, ptr) = self.get_ptr_place::<T>()?;
+        let reservation = Reservation::new(self, place, core::mem::size_of::<T>());
 
         T::init(ptr, T::InitArg::default());
 
-        self.add_to_drop_queue::<T>(place);
+        reservation.keep();
+        slot.fill(place, TypeInfo::of::<T>());
 
         Some(unsafe {
             ptr::from_ref(ptr)
@@ -169,12 +1026,21 @@ impl<'a, const SIZE: usize> Arena<SIZE> {
     /// acquire a reference to a value of type T that can be initialized with
     /// the Init trait, using a given InitArg.
     /// This is useful for types that require initialization.
+    /// If `T::init` panics, the value is never dropped and its room is handed back.
+    ///
+    /// Under Stacked Borrows, as Miri checks by default, writing to the value through
+    /// the returned reference, e.g. to a `Cell`, invalidates the references the value
+    /// holds to itself. Reach it through one of those instead, if it is written to.
+    #[cfg_attr(feature = "record", track_caller)]
     pub fn acquire_init<T: Init>(&'a self, arg: T::InitArg) -> Option<&'a T> {
+        let mut slot = self.claim_drop_slot_for::<T>()?;
         let (place, ptr) = self.get_ptr_place::<T>()?;
+        let reservation = Reservation::new(self, place, core::mem::size_of::<T>());
 
         T::init(ptr, arg);
 
-        self.add_to_drop_queue::<T>(place);
+        reservation.keep();
+        slot.fill(place, TypeInfo::of::<T>());
 
         Some(unsafe {
             ptr::from_ref(ptr)
@@ -186,12 +1052,16 @@ impl<'a, const SIZE: usize> Arena<SIZE> {
 
     /// acquire a reference to a value of type T that is initialized with it's default value.
     /// This is useful for types that do not require initialization.
+    #[cfg_attr(feature = "record", track_caller)]
     pub fn acquire_default<T: Default>(&'a self) -> Option<&'a T> {
+        let mut slot = self.claim_drop_slot_for::<T>()?;
         let (place, ptr) = self.get_ptr_place::<T>()?;
+        let reservation = Reservation::new(self, place, core::mem::size_of::<T>());
 
         ptr.write(T::default());
 
-        self.add_to_drop_queue::<T>(place);
+        reservation.keep();
+        slot.fill(place, TypeInfo::of::<T>());
 
         Some(unsafe {
             ptr::from_ref(ptr)
@@ -203,30 +1073,155 @@ impl<'a, const SIZE: usize> Arena<SIZE> {
 
     /// acquire a reference to a value of type T that is initialized with the given value.
     /// This is useful for types that do not require initialization.
+    #[cfg_attr(feature = "record", track_caller)]
     pub fn acquire<T>(&'a self, val: T) -> Option<&'a T> {
-        let (place, ptr) = self.get_ptr_place::<T>()?;
+        self.try_acquire(val).ok()
+    }
+
+    /// acquire a reference to a value of type T that is initialized with the given value,
+    /// handing the value back if the arena has no room for it or its drop queue is full.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn try_acquire<T>(&'a self, val: T) -> Result<&'a T, T> {
+        self.try_acquire_mut(val).map(|val| &*val)
+    }
 
-        ptr.write(val);
+    /// acquire a mutable reference to a value of type T that is initialized with the given value,
+    /// handing the value back if the arena has no room for it.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "record", track_caller)]
+    pub(crate) fn try_acquire_mut<T>(&'a self, val: T) -> Result<&'a mut T, T> {
+        let Some(mut slot) = self.claim_drop_slot_for::<T>() else {
+            return Err(val);
+        };
+        let Some((place, ptr)) = self.get_ptr_place::<T>() else {
+            return Err(val);
+        };
 
-        self.add_to_drop_queue::<T>(place);
+        let val = ptr.write(val);
+
+        slot.fill(place, TypeInfo::of::<T>());
+
+        Ok(val)
+    }
+
+    /// acquire a reference to a value of type T wrapped in a ManuallyDrop.
+    /// The value is never added to the drop queue, so the arena will not run its destructor.
+    /// This is useful for values whose destruction the caller owns (e.g. handed off to hardware).
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_manual<T>(&'a self, val: T) -> Option<&'a ManuallyDrop<T>> {
+        let (_, ptr) = self.get_ptr_place::<ManuallyDrop<T>>()?;
+
+        ptr.write(ManuallyDrop::new(val));
 
         Some(unsafe {
             ptr::from_ref(ptr)
-                .cast::<T>()
+                .cast::<ManuallyDrop<T>>()
                 .as_ref()
                 .unwrap_unchecked()
         })
     }
 }
 
-impl<const SIZE: usize> Drop for Arena<SIZE> {
-    fn drop(&mut self) {
-        for pair in self.drop_queue.get_mut() {
-            let Some(Dropper { place, drop_func }) = pair else {
-                break;
-            };
-            let ptr = unsafe { self.backing_store.get().byte_add(*place) };
-            drop_func(ptr);
+impl DynArena {
+    /// The number of times the arena has been reset.
+    /// Stops counting at `usize::MAX`, which a target with a 16-bit `usize` can reach.
+    pub fn generation(&self) -> usize {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Run the arena's finalizers, drop every value in it and make its whole
+    /// capacity available again.
+    ///
+    /// If nothing acquired since the last reset needs dropping, this only moves the
+    /// arena's cursors back, however many values it holds.
+    pub fn reset(&mut self) {
+        unsafe { self.reset_unchecked() }
+    }
+
+    /// Drop every value in the drop queue and rewind the arena.
+    ///
+    /// # Safety
+    /// No references into the arena may be live and no acquisition may be in progress.
+    unsafe fn reset_unchecked(&self) {
+        self.rearm_watches();
+        // Saturates rather than wraps, so that a generation is never seen twice.
+        let _ = self
+            .generation
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |generation| {
+                generation.checked_add(1)
+            });
+        // Last, as a destructor that panics carries on from here.
+        unsafe { self.rewind_to(0, 0, 0) };
+    }
+
+    /// Run every finalizer kept past `store`, drop every value the drop queue holds
+    /// from entry `drops` on, and move the cursor of the backing store back to
+    /// `store`, with `padding` bytes of alignment padding before it.
+    ///
+    /// # Safety
+    /// No references to values past either point may be live and no acquisition
+    /// may be in progress.
+    unsafe fn rewind_to(&self, store: usize, drops: usize, padding: usize) {
+        let pass = DropPass::start(self, store, drops, padding);
+        unsafe { self.run_finalizers(store, &pass) };
+        let drop_queue = unsafe { self.storage.drop_queue().as_mut() }.unwrap();
+        let len = self.next_free_drop_spot.load(Ordering::Acquire).min(drop_queue.len());
+        // With nothing to drop the entries need not be looked at, which makes
+        // resetting an arena of plain data a matter of moving its cursors.
+        let must_drop = self.must_drop.load(Ordering::Acquire) || cfg!(feature = "lifetimes");
+        let entries = if must_drop {
+            &mut drop_queue[drops.min(len)..len]
+        } else {
+            &mut []
+        };
+        let drop_entry = |slot: &mut DropSlot| {
+            if let Some(dropper) = slot.0.take() {
+                #[cfg(feature = "lifetimes")]
+                self.note_drop_age(&dropper);
+                let ptr = unsafe { self.store().cast::<u8>().add(dropper.place) };
+                pass.call(|| (dropper.info.drop_func)(ptr));
+            }
+        };
+        // A parent is always queued before its children, so going through the
+        // children backwards drops the children of a child before it.
+        if !self.parents_first.load(Ordering::Relaxed) {
+            entries
+                .iter_mut()
+                .rev()
+                .filter(|slot| slot.is_child())
+                .for_each(drop_entry);
+        }
+        entries
+            .iter_mut()
+            .filter(|slot| !slot.is_child())
+            .for_each(drop_entry);
+        // Whatever is left is children that go after their parents.
+        entries.iter_mut().for_each(drop_entry);
+        if drops == 0 {
+            self.must_drop.store(false, Ordering::Relaxed);
+        }
+        self.next_free_drop_spot.store(drops, Ordering::Release);
+        #[cfg(feature = "alloc-info")]
+        self.published_drop_spots.store(drops, Ordering::Release);
+        self.next_free_store_spot.store(store, Ordering::Release);
+        self.padding.store(padding, Ordering::Relaxed);
+        if self.annotations_started() {
+            annotate::rewind(self.store().cast(), store, self.capacity());
+        }
+        pass.finish();
+    }
+
+    /// Drop every value in the arena as the storage behind it goes away.
+    fn finish(&mut self) {
+        #[cfg(feature = "canaries")]
+        if let Err(at) = self.check_canaries() {
+            panic!("arena canary at offset {at:#x} was overwritten");
+        }
+        #[cfg(feature = "leak-report")]
+        self.report_live_on_drop();
+        self.reset();
+        if self.annotations_started() {
+            annotate::finish(self.store().cast(), self.capacity());
         }
     }
 }
@@ -0,0 +1,127 @@
+//! How long the values of each type live in an arena, to tell values that might
+//! as well be permanent from those that could go in a scratch scope.
+
+use core::{fmt, time::Duration};
+
+use std::{
+    sync::{Mutex, OnceLock},
+    time::Instant,
+    vec::Vec,
+};
+
+use crate::{Dropper, DynArena};
+
+/// The upper bounds of the age buckets of [`Lifetimes::dropped`], from ten
+/// microseconds to ten seconds by factors of ten.
+pub const AGE_BUCKETS: [Duration; 7] = [
+    Duration::from_micros(10),
+    Duration::from_micros(100),
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+    Duration::from_secs(1),
+    Duration::from_secs(10),
+];
+
+/// How long the values of one type lived in an arena, from [`DynArena::lifetimes`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lifetimes {
+    /// The name of the type.
+    pub type_name: &'static str,
+    /// The number of values dropped younger than each bound of [`AGE_BUCKETS`] but
+    /// not the one before it, then the number dropped older than all of them.
+    pub dropped: [usize; AGE_BUCKETS.len() + 1],
+    /// The number of values still live.
+    pub live: usize,
+    /// The age of the oldest value still live.
+    pub oldest_live: Duration,
+}
+
+impl Lifetimes {
+    fn new(type_name: &'static str) -> Self {
+        Lifetimes {
+            type_name,
+            dropped: [0; AGE_BUCKETS.len() + 1],
+            live: 0,
+            oldest_live: Duration::ZERO,
+        }
+    }
+}
+
+/// The lifetimes of the values of every type an arena has dropped, in the order
+/// each type was first dropped.
+pub(crate) type DropAges = Mutex<Vec<Lifetimes>>;
+
+/// The entry for `type_name` in `lifetimes`, added if there is none.
+fn entry<'l>(lifetimes: &'l mut Vec<Lifetimes>, type_name: &'static str) -> &'l mut Lifetimes {
+    match lifetimes.iter().position(|l| l.type_name == type_name) {
+        Some(i) => &mut lifetimes[i],
+        None => {
+            lifetimes.push(Lifetimes::new(type_name));
+            lifetimes.last_mut().unwrap()
+        }
+    }
+}
+
+/// The moment ages are measured from, the first time one is taken.
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+/// The number of nanoseconds since [`EPOCH`], which a [`Dropper`] keeps as the
+/// moment its value was acquired, being half the size of an `Instant`.
+pub(crate) fn now() -> u64 {
+    EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+/// The age of a value acquired at `acquired`, as returned by [`now`].
+fn age(acquired: u64) -> Duration {
+    Duration::from_nanos(now().saturating_sub(acquired))
+}
+
+impl DynArena {
+    /// How long the values of each type have lived, those dropped so far, including
+    /// by resets, as a histogram of their ages at drop and those still live by count
+    /// and oldest age, e.g. to find values that are never dropped and belong in an
+    /// arena of their own.
+    ///
+    /// Slices of types that need no dropping are not tracked.
+    pub fn lifetimes(&self) -> Vec<Lifetimes> {
+        let mut lifetimes = self
+            .drop_ages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        for dropper in self.live_droppers() {
+            let lifetimes = entry(&mut lifetimes, (dropper.info.type_name)());
+            lifetimes.live += 1;
+            lifetimes.oldest_live = lifetimes.oldest_live.max(age(dropper.acquired));
+        }
+        lifetimes
+    }
+
+    /// Write [`DynArena::lifetimes`] as a table, one line per type.
+    pub fn report_lifetimes(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        writeln!(
+            w,
+            "  <10us <100us   <1ms  <10ms <100ms    <1s   <10s  >=10s   live  oldest  type"
+        )?;
+        for lifetimes in self.lifetimes() {
+            for dropped in lifetimes.dropped {
+                write!(w, "{dropped:>7}")?;
+            }
+            writeln!(
+                w,
+                "{:>7} {:>7.1?}  {}",
+                lifetimes.live, lifetimes.oldest_live, lifetimes.type_name
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Count the age of the value `dropper` drops, as it is dropped.
+    pub(crate) fn note_drop_age(&self, dropper: &Dropper) {
+        let age = age(dropper.acquired);
+        let bucket = AGE_BUCKETS.partition_point(|bound| *bound <= age);
+        let mut lifetimes = self.drop_ages.lock().unwrap_or_else(|e| e.into_inner());
+        entry(&mut lifetimes, (dropper.info.type_name)()).dropped[bucket] += 1;
+    }
+}
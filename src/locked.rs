@@ -0,0 +1,74 @@
+//! Arenas whose memory is locked into RAM, for host-side code that keeps key
+//! material in an arena and must not have it swapped to disk.
+
+use core::{
+    mem,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+use std::io;
+
+use crate::{sys, Arena};
+
+/// An arena placed in mapped memory that is locked into RAM with `mlock` for as
+/// long as the arena lives, so that nothing acquired from it is written to swap.
+///
+/// Locking is subject to the process's limit on locked memory (`ulimit -l`).
+/// Pair it with [`DynArena::acquire_secret`] to zero keys once they are dropped.
+///
+/// Derefs to the [`Arena`] it contains.
+///
+/// [`DynArena::acquire_secret`]: crate::DynArena::acquire_secret
+pub struct LockedArena<const SIZE: usize> {
+    mapping: NonNull<u8>,
+    len: usize,
+    arena: NonNull<Arena<SIZE>>,
+}
+
+unsafe impl<const SIZE: usize> Sync for LockedArena<SIZE> {}
+unsafe impl<const SIZE: usize> Send for LockedArena<SIZE> {}
+
+impl<const SIZE: usize> LockedArena<SIZE> {
+    /// Map a new arena with a fixed size buffer of SIZE bytes and lock it into RAM.
+    pub fn new() -> io::Result<Self> {
+        let len = mem::size_of::<Arena<SIZE>>()
+            .max(1)
+            .next_multiple_of(sys::page_size());
+        let mapping = sys::map(len)?;
+        if let Err(err) = unsafe { sys::lock(mapping, len) } {
+            unsafe { sys::unmap(mapping, len) };
+            return Err(err);
+        }
+        let arena = mapping.cast::<Arena<SIZE>>();
+        unsafe { arena.write(Arena::new()) };
+        Ok(LockedArena {
+            mapping,
+            len,
+            arena,
+        })
+    }
+}
+
+impl<const SIZE: usize> Deref for LockedArena<SIZE> {
+    type Target = Arena<SIZE>;
+
+    fn deref(&self) -> &Arena<SIZE> {
+        unsafe { self.arena.as_ref() }
+    }
+}
+
+impl<const SIZE: usize> DerefMut for LockedArena<SIZE> {
+    fn deref_mut(&mut self) -> &mut Arena<SIZE> {
+        unsafe { self.arena.as_mut() }
+    }
+}
+
+impl<const SIZE: usize> Drop for LockedArena<SIZE> {
+    fn drop(&mut self) {
+        unsafe {
+            self.arena.drop_in_place();
+            sys::unlock(self.mapping, self.len);
+            sys::unmap(self.mapping, self.len);
+        }
+    }
+}
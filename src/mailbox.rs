@@ -0,0 +1,148 @@
+//! A single-producer single-consumer channel of byte messages kept in an arena.
+
+use core::{cell::UnsafeCell, fmt, slice};
+
+use crate::{
+    atomic::{AtomicUsize, Ordering},
+    DynArena,
+};
+
+/// A channel of up to N pending byte messages of any length, whose payloads are
+/// kept in an arena and passed along as offsets into it, e.g. from an interrupt
+/// handler to a task.
+///
+/// Split it into a [`Sender`] and a [`Receiver`] to use it. The space of received
+/// messages is only reclaimed when the arena is reset.
+pub struct Mailbox<'a, const N: usize> {
+    arena: &'a DynArena,
+    // The offset and length of each pending message, written by the sender before it
+    // publishes it by advancing `tail`.
+    slots: [UnsafeCell<(usize, usize)>; N],
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+unsafe impl<const N: usize> Sync for Mailbox<'_, N> {}
+
+/// The sending half of a [`Mailbox`].
+pub struct Sender<'m, 'a, const N: usize> {
+    mailbox: &'m Mailbox<'a, N>,
+}
+
+/// The receiving half of a [`Mailbox`].
+pub struct Receiver<'m, 'a, const N: usize> {
+    mailbox: &'m Mailbox<'a, N>,
+}
+
+impl<'a, const N: usize> Mailbox<'a, N> {
+    /// Create a new mailbox with room for N pending messages kept in `arena`.
+    #[must_use]
+    pub const fn new(arena: &'a DynArena) -> Self {
+        Mailbox {
+            arena,
+            slots: [const { UnsafeCell::new((0, 0)) }; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    /// Split the mailbox into its sending and receiving halves.
+    pub fn split(&mut self) -> (Sender<'_, 'a, N>, Receiver<'_, 'a, N>) {
+        (Sender { mailbox: self }, Receiver { mailbox: self })
+    }
+
+    /// The number of messages sent but not yet received.
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        self.tail.load(Ordering::Acquire).wrapping_sub(head)
+    }
+
+    /// Whether there are no messages waiting to be received.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Why [`Sender::send`] failed, with the message given back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SendError<'m> {
+    /// The mailbox already held as many messages as it can, until one is received.
+    Full(&'m [u8]),
+    /// The arena did not have room for a copy of the message.
+    OutOfSpace(&'m [u8]),
+}
+
+impl<'m> SendError<'m> {
+    /// The message that was not sent.
+    pub fn into_inner(self) -> &'m [u8] {
+        match self {
+            SendError::Full(msg) | SendError::OutOfSpace(msg) => msg,
+        }
+    }
+}
+
+impl fmt::Display for SendError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::Full(_) => f.write_str("mailbox is full"),
+            SendError::OutOfSpace(_) => f.write_str("arena is out of space"),
+        }
+    }
+}
+
+impl<'a, const N: usize> Sender<'_, 'a, N> {
+    /// Copy `msg` into the arena and send it, giving it back if there is no room
+    /// for it in either the mailbox or the arena.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn send<'m>(&mut self, msg: &'m [u8]) -> Result<(), SendError<'m>> {
+        if self.is_full() {
+            return Err(SendError::Full(msg));
+        }
+        let copy = self
+            .mailbox
+            .arena
+            .concat_slices(&[msg])
+            .ok_or(SendError::OutOfSpace(msg))?;
+        self.send_ref(copy).map_err(|_| SendError::Full(msg))
+    }
+
+    /// Send a message that is already in the arena, e.g. one written into it
+    /// through [`DynArena::begin_write`], without copying it.
+    /// Gives it back if the mailbox is full or it is not in the arena.
+    pub fn send_ref(&mut self, msg: &'a [u8]) -> Result<(), &'a [u8]> {
+        let mailbox = self.mailbox;
+        let store = mailbox.arena.store();
+        let place = if msg.is_empty() {
+            0
+        } else {
+            msg.as_ptr().addr().wrapping_sub(store.addr())
+        };
+        if self.is_full() || place > store.len() || msg.len() > store.len() - place {
+            return Err(msg);
+        }
+        let tail = mailbox.tail.load(Ordering::Relaxed);
+        unsafe { *mailbox.slots[tail % N].get() = (place, msg.len()) };
+        mailbox.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Whether the mailbox has no room for another message.
+    pub fn is_full(&self) -> bool {
+        self.mailbox.len() == N
+    }
+}
+
+impl<'a, const N: usize> Receiver<'_, 'a, N> {
+    /// Receive the oldest message sent, if there is one.
+    pub fn recv(&mut self) -> Option<&'a [u8]> {
+        let mailbox = self.mailbox;
+        let head = mailbox.head.load(Ordering::Relaxed);
+        if mailbox.tail.load(Ordering::Acquire) == head {
+            return None;
+        }
+        let (place, len) = unsafe { *mailbox.slots[head % N].get() };
+        mailbox.head.store(head.wrapping_add(1), Ordering::Release);
+        let base = mailbox.arena.store().cast::<u8>();
+        Some(unsafe { slice::from_raw_parts(base.add(place), len) })
+    }
+}
@@ -0,0 +1,76 @@
+//! Values that live for the rest of the program, acquired from a `static` arena,
+//! for driver state and the tasks and channels of an embassy executor, or any
+//! other API that asks for `'static` references.
+//!
+//! Every acquisition from a `static` arena already returns a `&'static T`, since
+//! a shared borrow of it lasts forever and it can never be reset. These methods
+//! hand out `&'static mut T` as well, mirroring `static_cell::StaticCell`, but
+//! one arena replaces a `StaticCell` (or a `static mut`) per value:
+//!
+//! ```ignore
+//! use arena_alloc::Arena;
+//! use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel};
+//!
+//! static ARENA: Arena<4096> = Arena::new();
+//!
+//! #[embassy_executor::task]
+//! async fn blink(led: &'static mut Output<'static>, ticks: &'static Channel<CriticalSectionRawMutex, u32, 4>) {
+//!     loop {
+//!         Timer::after_millis(ticks.receive().await.into()).await;
+//!         led.toggle();
+//!     }
+//! }
+//!
+//! #[embassy_executor::main]
+//! async fn main(spawner: Spawner) {
+//!     let p = embassy_stm32::init(Default::default());
+//!     let led = ARENA.make_static(Output::new(p.PA5, Level::Low, Speed::Low));
+//!     let ticks = ARENA.make_static(Channel::new());
+//!     spawner.spawn(blink(led, ticks)).unwrap();
+//!     ticks.send(500).await;
+//! }
+//! ```
+
+use crate::{DynArena, Reservation, TypeInfo};
+
+impl DynArena {
+    /// acquire a mutable reference to `val` that lives as long as the arena,
+    /// like `StaticCell::init`.
+    ///
+    /// # Panics
+    /// Panics if the arena is out of space, which at init time is a sizing mistake.
+    #[track_caller]
+    #[allow(clippy::mut_from_ref)]
+    pub fn make_static<T>(&'static self, val: T) -> &'static mut T {
+        self.make_static_with(|| val)
+    }
+
+    /// Like [`DynArena::make_static`], but builds the value with `f` only once
+    /// there is room for it.
+    ///
+    /// # Panics
+    /// Panics if the arena is out of space or its drop queue is full.
+    #[track_caller]
+    #[allow(clippy::mut_from_ref)]
+    pub fn make_static_with<T>(&'static self, f: impl FnOnce() -> T) -> &'static mut T {
+        let Some(mut slot) = self.claim_drop_slot_for::<T>() else {
+            panic!("arena drop queue is full");
+        };
+        let Some((place, ptr)) = self.get_ptr_place::<T>() else {
+            panic!("arena is out of space for a {}", core::any::type_name::<T>());
+        };
+        let reservation = Reservation::new(self, place, core::mem::size_of::<T>());
+        let val = ptr.write(f());
+        reservation.keep();
+        slot.fill(place, TypeInfo::of::<T>());
+        val
+    }
+
+    /// acquire a mutable reference to `val` that lives as long as the arena,
+    /// handing the value back if the arena has no room for it.
+    #[cfg_attr(feature = "record", track_caller)]
+    #[allow(clippy::mut_from_ref)]
+    pub fn try_make_static<T>(&'static self, val: T) -> Result<&'static mut T, T> {
+        self.try_acquire_mut(val)
+    }
+}
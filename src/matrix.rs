@@ -0,0 +1,122 @@
+//! Two-dimensional arrays of values acquired from an arena.
+
+use core::ops::{Index, IndexMut};
+
+use crate::DynArena;
+
+/// A matrix of values of type T kept row after row in one slice of an arena,
+/// e.g. the samples of a multichannel DSP block or the pixels of an image.
+///
+/// Index it with `(row, col)`.
+pub struct Matrix<'a, T> {
+    data: &'a mut [T],
+    rows: usize,
+    cols: usize,
+}
+
+impl<'a, T> Matrix<'a, T> {
+    /// The number of rows.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The number of columns.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// The values of row `i`.
+    ///
+    /// # Panics
+    /// Panics if `i` is out of bounds.
+    pub fn row(&self, i: usize) -> &[T] {
+        &self.data[i * self.cols..][..self.cols]
+    }
+
+    /// The values of row `i`, mutably.
+    ///
+    /// # Panics
+    /// Panics if `i` is out of bounds.
+    pub fn row_mut(&mut self, i: usize) -> &mut [T] {
+        &mut self.data[i * self.cols..][..self.cols]
+    }
+
+    /// Every row, in order.
+    pub fn iter_rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.rows).map(|i| self.row(i))
+    }
+
+    /// Every row mutably, in order.
+    pub fn iter_rows_mut(&mut self) -> impl Iterator<Item = &mut [T]> {
+        // chunks_exact_mut can not make chunks of zero length, so empty rows are made here.
+        let (rows, cols) = (self.rows, self.cols);
+        let mut rest = &mut *self.data;
+        (0..rows).map(move |_| {
+            let (row, after) = core::mem::take(&mut rest).split_at_mut(cols);
+            rest = after;
+            row
+        })
+    }
+
+    /// The value at `row` and `col`, if they are in bounds.
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        self.data.get(row * self.cols + col)
+    }
+
+    /// Every value, row after row.
+    pub fn as_slice(&self) -> &[T] {
+        self.data
+    }
+
+    /// Every value row after row, mutably.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self.data
+    }
+
+    /// Every value row after row, borrowing the arena rather than the matrix.
+    pub fn into_slice(self) -> &'a mut [T] {
+        self.data
+    }
+}
+
+impl<T> Index<(usize, usize)> for Matrix<'_, T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        &self.row(row)[col]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<'_, T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        &mut self.row_mut(row)[col]
+    }
+}
+
+impl DynArena {
+    /// acquire a matrix of `rows` by `cols` values of type T, each initialized with
+    /// its default value.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_matrix<T: Default>(&self, rows: usize, cols: usize) -> Option<Matrix<'_, T>> {
+        self.acquire_matrix_fill_with(rows, cols, |_, _| T::default())
+    }
+
+    /// acquire a matrix of `rows` by `cols` values of type T, each initialized with
+    /// the result of calling `f` with its row and column.
+    /// If `f` panics, the values it has already returned are dropped.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_matrix_fill_with<T>(
+        &self,
+        rows: usize,
+        cols: usize,
+        mut f: impl FnMut(usize, usize) -> T,
+    ) -> Option<Matrix<'_, T>> {
+        let data = self.acquire_slice_try_fill_with(rows.checked_mul(cols)?, |i| {
+            Some(f(i / cols, i % cols))
+        })?;
+        Some(Matrix { data, rows, cols })
+    }
+}
@@ -0,0 +1,172 @@
+//! Typed offsets of values in an arena, for locating them without a pointer, and
+//! with the `type-tags` feature the types of the values found at them.
+
+#[cfg(feature = "type-tags")]
+use core::{any::TypeId, mem::size_of};
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+#[cfg(feature = "type-tags")]
+use crate::atomic;
+use crate::{DynArena, TypeInfo};
+
+/// The place of a value of type T in an arena, as the number of bytes from the
+/// start of its backing store.
+///
+/// Unlike a reference it is plain data that means the same in any address
+/// space, e.g. to log, to persist alongside a snapshot of the arena, or to post
+/// to another core that maps the arena elsewhere.
+#[repr(transparent)]
+pub struct Offset<T> {
+    offset: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Offset<T> {
+    /// The offset `offset` bytes into an arena, e.g. as read back from a log.
+    pub const fn from_raw(offset: usize) -> Self {
+        Offset {
+            offset,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The number of bytes from the start of the arena to the value.
+    pub const fn get(self) -> usize {
+        self.offset
+    }
+}
+
+impl<T> Clone for Offset<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Offset<T> {}
+
+impl<T> PartialEq for Offset<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.offset == other.offset
+    }
+}
+
+impl<T> Eq for Offset<T> {}
+
+impl<T> PartialOrd for Offset<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Offset<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.offset.cmp(&other.offset)
+    }
+}
+
+impl<T> Hash for Offset<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.offset.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Offset<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Offset({:#x})", self.offset)
+    }
+}
+
+impl DynArena {
+    /// acquire a reference to a value of type T that is initialized with the given
+    /// value, along with its offset in the arena.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_with_offset<T>(&self, val: T) -> Option<(&T, Offset<T>)> {
+        let mut slot = self.claim_drop_slot_for::<T>()?;
+        let (place, ptr) = self.get_ptr_place::<T>()?;
+        let val = ptr.write(val);
+        slot.fill(place, TypeInfo::of::<T>());
+        Some((val, Offset::from_raw(place)))
+    }
+
+    /// The value at `offset` in the arena.
+    ///
+    /// # Safety
+    /// `offset` must be that of a value of type T acquired from this arena since it
+    /// was last reset, which is still live.
+    pub unsafe fn resolve<T>(&self, offset: Offset<T>) -> &T {
+        unsafe { &*self.store().cast::<u8>().add(offset.get()).cast::<T>() }
+    }
+}
+
+#[cfg(feature = "type-tags")]
+impl TypeInfo {
+    /// The info for a value of type T acquired with its type tag.
+    fn of_tagged<T: 'static>() -> &'static TypeInfo {
+        const {
+            &TypeInfo {
+                type_id: Some(TypeId::of::<T>),
+                ..*TypeInfo::of::<T>()
+            }
+        }
+    }
+}
+
+#[cfg(feature = "type-tags")]
+impl DynArena {
+    /// acquire a reference to a value of type T that is initialized with the given
+    /// value, along with its offset in the arena, tagged with its type so that
+    /// [`DynArena::get`] can look it up.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_tagged<T: 'static>(&self, val: T) -> Option<(&T, Offset<T>)> {
+        // Even values with nothing to drop take an entry, which holds the tag.
+        let mut slot = self.claim_drop_slots(1)?;
+        let (place, ptr) = self.get_ptr_place::<T>()?;
+        let val = ptr.write(val);
+        slot.fill(place, TypeInfo::of_tagged::<T>());
+        Some((val, Offset::from_raw(place)))
+    }
+
+    /// The value at `offset`, if the offset is in bounds, aligned for a T, and the
+    /// place of a T acquired with [`DynArena::acquire_tagged`] that is still live,
+    /// e.g. for an offset handed over from another module or read back from a log.
+    ///
+    /// Takes time in proportion to the number of values in the arena.
+    pub fn get<T: 'static>(&self, offset: Offset<T>) -> Option<&T> {
+        let place = offset.get();
+        let end = place.checked_add(size_of::<T>())?;
+        let ptr = self.store().cast::<u8>().wrapping_add(place).cast::<T>();
+        let used = self.next_free_store_spot.load(atomic::Ordering::Acquire);
+        if end > used || !ptr.is_aligned() {
+            return None;
+        }
+        let tagged = self.tags_at(place).any(|id| id == TypeId::of::<T>());
+        // A live entry tagged with T means a T is there, and the borrow of the
+        // arena keeps it there, as only a reset or a rewind drops it.
+        tagged.then(|| unsafe { &*ptr })
+    }
+
+    /// The type of the value acquired with [`DynArena::acquire_tagged`] that is live
+    /// at `offset` bytes into the arena, if there is one, e.g. for a debugger or a
+    /// tool walking the arena, or to check where an offset from elsewhere points.
+    ///
+    /// Takes time in proportion to the number of values in the arena.
+    pub fn type_of(&self, offset: usize) -> Option<TypeId> {
+        self.tags_at(offset).next()
+    }
+
+    /// The types of the live tagged values at `place`, the last acquired first.
+    /// Zero-sized values can share a place with each other and another value.
+    fn tags_at(&self, place: usize) -> impl Iterator<Item = TypeId> + '_ {
+        self.published_droppers()
+            .iter()
+            .rev()
+            .filter_map(|slot| slot.0.as_ref())
+            .filter(move |dropper| dropper.place == place)
+            .filter_map(|dropper| dropper.info.type_id.map(|id| id()))
+    }
+}
@@ -0,0 +1,101 @@
+//! Packet buffers with room to add headers and trailers around their payload.
+
+use core::ops::{Deref, DerefMut};
+
+use crate::DynArena;
+
+/// The bytes of a packet in an arena, with headroom before them and tailroom
+/// after them, so that a network stack can add protocol headers and trailers
+/// on the way down without copying the payload.
+///
+/// Derefs to the bytes of the packet.
+pub struct PacketBuf<'a> {
+    buf: &'a mut [u8],
+    start: usize,
+    end: usize,
+}
+
+impl<'a> PacketBuf<'a> {
+    /// acquire a packet of `payload` from `arena`, with room for `headroom` bytes
+    /// of headers before it and `tailroom` bytes of trailers after it.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn new(
+        arena: &'a DynArena,
+        headroom: usize,
+        payload: &[u8],
+        tailroom: usize,
+    ) -> Option<Self> {
+        let start = headroom;
+        let end = start.checked_add(payload.len())?;
+        let buf = arena.acquire_slice_try_fill_with(end.checked_add(tailroom)?, |_| Some(0))?;
+        buf[start..end].copy_from_slice(payload);
+        Some(PacketBuf { buf, start, end })
+    }
+
+    /// The number of bytes that can still be added before the packet.
+    pub fn headroom(&self) -> usize {
+        self.start
+    }
+
+    /// The number of bytes that can still be added after the packet.
+    pub fn tailroom(&self) -> usize {
+        self.buf.len() - self.end
+    }
+
+    /// Add `header` to the front of the packet.
+    /// Returns whether there was headroom for it.
+    pub fn push_header(&mut self, header: &[u8]) -> bool {
+        let Some(start) = self.start.checked_sub(header.len()) else {
+            return false;
+        };
+        self.buf[start..self.start].copy_from_slice(header);
+        self.start = start;
+        true
+    }
+
+    /// Remove the first `len` bytes of the packet, e.g. a header that has been parsed,
+    /// and return them. They become headroom again.
+    pub fn pull_header(&mut self, len: usize) -> Option<&[u8]> {
+        if len > self.len() {
+            return None;
+        }
+        self.start += len;
+        Some(&self.buf[self.start - len..self.start])
+    }
+
+    /// Add `trailer` to the end of the packet.
+    /// Returns whether there was tailroom for it.
+    pub fn push_trailer(&mut self, trailer: &[u8]) -> bool {
+        if trailer.len() > self.tailroom() {
+            return false;
+        }
+        self.buf[self.end..self.end + trailer.len()].copy_from_slice(trailer);
+        self.end += trailer.len();
+        true
+    }
+
+    /// Shorten the packet to its first `len` bytes, e.g. to drop padding or a checked
+    /// trailer. The rest become tailroom again. Does nothing if it is no longer than `len`.
+    pub fn trim(&mut self, len: usize) {
+        self.end = self.end.min(self.start.saturating_add(len));
+    }
+
+    /// The bytes of the packet, which borrow the arena rather than the buffer.
+    pub fn into_bytes(self) -> &'a mut [u8] {
+        &mut self.buf[self.start..self.end]
+    }
+}
+
+impl Deref for PacketBuf<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buf[self.start..self.end]
+    }
+}
+
+impl DerefMut for PacketBuf<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.buf[self.start..self.end]
+    }
+}
@@ -0,0 +1,30 @@
+//! Whole pages of memory, for page tables, buffer caches and DMA buffers that
+//! must start on a page boundary and span whole pages.
+
+use core::slice;
+
+use crate::DynArena;
+
+impl DynArena {
+    /// acquire `n` zeroed pages of PAGE bytes each, the first aligned to PAGE bytes,
+    /// e.g. 4096 for the page tables of most MMUs. PAGE must be a power of two.
+    ///
+    /// Like byte buffers, pages skip the drop queue.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_pages<const PAGE: usize>(&self, n: usize) -> Option<&mut [[u8; PAGE]]> {
+        const { assert!(PAGE.is_power_of_two(), "PAGE must be a power of two") };
+        let size = PAGE.checked_mul(n)?;
+        let place = self.reserve(size, PAGE);
+        #[cfg(feature = "record")]
+        self.record_layout(
+            core::any::type_name::<[[u8; PAGE]]>(),
+            size,
+            PAGE,
+            place.is_some(),
+        );
+        let pages = unsafe { self.store().cast::<u8>().add(place?) };
+        unsafe { pages.write_bytes(0, size) };
+        Some(unsafe { slice::from_raw_parts_mut(pages.cast(), n) })
+    }
+}
@@ -0,0 +1,82 @@
+//! Parsing input bytes into trees kept in an arena, borrowing spans of the input.
+
+use core::fmt;
+
+use crate::DynArena;
+
+/// A parsed value and the rest of the input after it, or why parsing failed.
+type Parsed<'a, T, E> = Result<(T, &'a [u8]), E>;
+
+/// A value that can be parsed from the start of some input, acquiring its nodes
+/// from an arena and borrowing what it leaves unparsed (strings, byte fields,
+/// nested messages decoded later) straight from the input, e.g. a JSON, CBOR or
+/// protobuf decoder without `alloc`.
+pub trait ParseIn<'a>: Sized {
+    /// The error the parser can fail with.
+    type Error;
+
+    /// Parse a value from the start of `input`, acquiring any nodes it needs from `arena`.
+    /// Returns the value and the rest of the input.
+    fn parse_in(arena: &'a DynArena, input: &'a [u8]) -> Parsed<'a, Self, Self::Error>;
+}
+
+/// Why [`DynArena::parse`] or [`DynArena::parse_seq`] failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError<E> {
+    /// The arena did not have room for the parsed values.
+    OutOfSpace,
+    /// The parser failed.
+    Parse(E),
+    /// The input went on for this many bytes after the value.
+    TrailingBytes(usize),
+}
+
+impl<E: fmt::Display> fmt::Display for ParseError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::OutOfSpace => f.write_str("arena is out of space"),
+            ParseError::Parse(e) => write!(f, "parse failed: {e}"),
+            ParseError::TrailingBytes(len) => write!(f, "{len} bytes after the value"),
+        }
+    }
+}
+
+impl<'a> DynArena {
+    /// Parse all of `input` as a T and acquire a reference to it.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn parse<T: ParseIn<'a>>(&'a self, input: &'a [u8]) -> Result<&'a T, ParseError<T::Error>> {
+        let (val, rest) = T::parse_in(self, input).map_err(ParseError::Parse)?;
+        if !rest.is_empty() {
+            return Err(ParseError::TrailingBytes(rest.len()));
+        }
+        self.acquire(val).ok_or(ParseError::OutOfSpace)
+    }
+
+    /// Parse `count` Ts one after another from the start of `input`, e.g. the elements of
+    /// a CBOR array or a repeated protobuf field, and acquire a slice of them.
+    /// Returns the slice and the rest of the input.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn parse_seq<T: ParseIn<'a>>(
+        &'a self,
+        input: &'a [u8],
+        count: usize,
+    ) -> Parsed<'a, &'a [T], ParseError<T::Error>> {
+        let mut rest = input;
+        let mut error = None;
+        let seq = self.acquire_slice_try_fill_with(count, |_| match T::parse_in(self, rest) {
+            Ok((val, after)) => {
+                rest = after;
+                Some(val)
+            }
+            Err(e) => {
+                error = Some(e);
+                None
+            }
+        });
+        match (seq, error) {
+            (Some(seq), _) => Ok((seq, rest)),
+            (None, Some(e)) => Err(ParseError::Parse(e)),
+            (None, None) => Err(ParseError::OutOfSpace),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Blocks of per-CPU data, one copy of a value per core, each on a cache line of
+//! its own so that cores updating their own copies never contend.
+
+use core::ops::Index;
+
+use crate::DynArena;
+
+/// A value aligned and padded to a cache line of its own, taken to be 64 bytes.
+#[repr(C, align(64))]
+struct CacheLine<T>(T);
+
+/// One copy of a value of type T per CPU, acquired with [`DynArena::acquire_per_cpu`]
+/// and indexed by CPU id.
+///
+/// Copies are shared between cores, so T should be a type that can be updated
+/// through a shared reference, such as an atomic.
+#[repr(transparent)]
+pub struct PerCpu<T>([CacheLine<T>]);
+
+impl<T> PerCpu<T> {
+    /// The copy of the CPU with id `cpu`, if there is one.
+    pub fn get(&self, cpu: usize) -> Option<&T> {
+        self.0.get(cpu).map(|line| &line.0)
+    }
+
+    /// The number of CPUs with a copy.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether no CPU has a copy.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The copies of every CPU, by CPU id, e.g. to sum per-core counters.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.0.iter().map(|line| &line.0)
+    }
+}
+
+impl<T> Index<usize> for PerCpu<T> {
+    type Output = T;
+
+    fn index(&self, cpu: usize) -> &T {
+        &self.0[cpu].0
+    }
+}
+
+impl DynArena {
+    /// acquire a default value of type T for each of `ncpus` CPUs, each on a
+    /// cache line of its own so that cores updating their own copies do not
+    /// falsely share lines, e.g. per-core counters or run queues.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_per_cpu<T: Default>(&self, ncpus: usize) -> Option<&PerCpu<T>> {
+        let lines = self.acquire_slice_fill_with(ncpus, |_| CacheLine(T::default()))?;
+        Some(unsafe { &*(lines as *const [CacheLine<T>] as *const PerCpu<T>) })
+    }
+}
@@ -0,0 +1,60 @@
+//! Slices of bytemuck `Pod` types viewed as bytes and back.
+//!
+//! Casting a slice of Ts to bytes is always allowed and is left to
+//! `bytemuck::cast_slice`. The other way round needs the bytes to be aligned
+//! for T, which byte buffers are not in general, so these place them where
+//! they are.
+
+use core::{mem::size_of, slice};
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::DynArena;
+
+impl DynArena {
+    /// acquire a slice of `len` Ts with every byte zero, e.g. vertices or samples
+    /// to be filled in place through `bytemuck::cast_slice_mut`.
+    // Every call reserves a disjoint region of the backing store, so handing out
+    // a mutable reference to it from a shared borrow of the arena is sound.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_pod_zeroed<T: Zeroable>(&self, len: usize) -> Option<&mut [T]> {
+        let (_, start) = self.reserve_slice::<T>(len)?;
+        unsafe { start.write_bytes(0, len) };
+        Some(unsafe { slice::from_raw_parts_mut(start, len) })
+    }
+
+    /// acquire a slice of Ts holding a copy of `bytes`, which need not be aligned.
+    /// Returns None if the length of `bytes` is not a multiple of the size of a T
+    /// or the arena has no room.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_pod_from_bytes<T: Pod>(&self, bytes: &[u8]) -> Option<&mut [T]> {
+        let len = bytes.len().checked_div(size_of::<T>()).unwrap_or(0);
+        if len * size_of::<T>() != bytes.len() {
+            return None;
+        }
+        let (_, start) = self.reserve_slice::<T>(len)?;
+        unsafe {
+            start
+                .cast::<u8>()
+                .copy_from_nonoverlapping(bytes.as_ptr(), bytes.len())
+        };
+        Some(unsafe { slice::from_raw_parts_mut(start, len) })
+    }
+
+    /// `bytes` viewed as a slice of Ts where they are if they are aligned for T,
+    /// or else a copy of them acquired from the arena.
+    /// Returns None if the length of `bytes` is not a multiple of the size of a T
+    /// or a copy is needed and the arena has no room.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn cast_pod_slice<'a, T: Pod>(&'a self, bytes: &'a [u8]) -> Option<&'a [T]> {
+        match bytemuck::try_cast_slice(bytes) {
+            Ok(vals) => Some(vals),
+            Err(bytemuck::PodCastError::TargetAlignmentGreaterAndInputNotAligned) => {
+                self.acquire_pod_from_bytes(bytes).map(|vals| &*vals)
+            }
+            Err(_) => None,
+        }
+    }
+}
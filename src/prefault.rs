@@ -0,0 +1,36 @@
+//! Touching every page of an arena up front, for host builds.
+
+use core::mem::{size_of, MaybeUninit};
+
+use crate::{sys, DropSlot, DynArena};
+
+/// Write every page that `len` bytes at `start` span, so that the host maps
+/// them in now rather than on first use. The bytes keep their values.
+///
+/// # Safety
+/// The range must be valid for reads and writes and not be accessed by anything
+/// else while it is touched.
+unsafe fn touch(start: *mut u8, len: usize) {
+    let start = start.cast::<MaybeUninit<u8>>();
+    let page = sys::page_size();
+    let mut at = 0;
+    while at < len {
+        unsafe {
+            let byte = start.add(at);
+            byte.write_volatile(byte.read_volatile());
+        }
+        at = (start.addr() + at + 1).next_multiple_of(page) - start.addr();
+    }
+}
+
+impl DynArena {
+    /// Touch every page of the backing store and drop queue, so that first
+    /// acquisitions do not pay for page faults, e.g. before a latency benchmark
+    /// or a soft real-time loop on an arena on the heap or in mapped memory.
+    pub fn prefault(&mut self) {
+        let store = self.store();
+        let drop_queue = self.storage.drop_queue();
+        unsafe { touch(store.cast(), store.len()) };
+        unsafe { touch(drop_queue.cast(), drop_queue.len() * size_of::<DropSlot>()) };
+    }
+}
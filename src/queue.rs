@@ -0,0 +1,163 @@
+//! A multi-producer multi-consumer queue whose nodes are kept in an arena.
+
+use core::{cell::UnsafeCell, mem::MaybeUninit};
+
+use crate::{
+    atomic::{AtomicUsize, Ordering},
+    DynArena,
+};
+
+/// A node of a [`Queue`], which holds one value while it is queued.
+struct Node<T> {
+    // Twice the position the node is ready to be pushed at, or one more than twice
+    // the position it was pushed at once it holds a value, so that the two never
+    // meet, even with a single node.
+    seq: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// A bounded lock-free queue of values of type T that any number of threads can
+/// push to and pop from, e.g. to hand out work between the cores of a dual-core
+/// MCU that share one `static` arena.
+///
+/// Its nodes are acquired from the arena once, when the queue is created, and are
+/// reused as values pass through it, so the queue never acquires more space.
+pub struct Queue<'a, T> {
+    nodes: &'a [Node<T>],
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+unsafe impl<T: Send> Send for Queue<'_, T> {}
+unsafe impl<T: Send> Sync for Queue<'_, T> {}
+
+impl<'a, T> Queue<'a, T> {
+    /// Create a new queue with room for `capacity` values, rounded up to a power of two,
+    /// whose nodes are acquired from `arena`.
+    /// Returns None if the capacity is zero or the arena is out of space.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn new(arena: &'a DynArena, capacity: usize) -> Option<Self> {
+        Self::starting_at(arena, capacity, 0)
+    }
+
+    /// Create a new queue whose first value is pushed and popped at position `start`,
+    /// e.g. to test the positions wrapping around.
+    // Positions wrap at `usize::MAX`, which only lands on the node after the last
+    // one if the number of nodes divides it, i.e. is a power of two.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub(crate) fn starting_at(arena: &'a DynArena, capacity: usize, start: usize) -> Option<Self> {
+        if capacity == 0 {
+            return None;
+        }
+        let mask = capacity.checked_next_power_of_two()? - 1;
+        let nodes = arena.acquire_slice_fill_with(mask + 1, |i| Node {
+            seq: AtomicUsize::new(start.wrapping_add(i.wrapping_sub(start) & mask).wrapping_mul(2)),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        })?;
+        Some(Queue {
+            nodes,
+            head: AtomicUsize::new(start),
+            tail: AtomicUsize::new(start),
+        })
+    }
+
+    /// The number of values the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The node for the value at `position`.
+    fn node(&self, position: usize) -> &Node<T> {
+        &self.nodes[position & (self.nodes.len() - 1)]
+    }
+
+    /// Push `val` onto the back of the queue, handing it back if the queue is full.
+    pub fn push(&self, val: T) -> Result<(), T> {
+        let mut tail = self.tail.load(Ordering::Relaxed);
+        loop {
+            let node = self.node(tail);
+            let seq = node.seq.load(Ordering::Acquire);
+            match seq.wrapping_sub(tail.wrapping_mul(2)) as isize {
+                0 => match self.tail.compare_exchange_weak(
+                    tail,
+                    tail.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        unsafe { (*node.value.get()).write(val) };
+                        node.seq.store(tail.wrapping_mul(2) | 1, Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => tail = current,
+                },
+                // The node still holds the value pushed a lap ago.
+                lag if lag < 0 => return Err(val),
+                _ => tail = self.tail.load(Ordering::Relaxed),
+            }
+        }
+    }
+
+    /// Push `val` onto the back of the queue, calling `wait` each time the queue is
+    /// full until a consumer has popped a value to make room, for producers that
+    /// would rather be held back than drop data. `wait` is the wait strategy, e.g.
+    /// `core::hint::spin_loop`, a `wfe` or an RTOS yield, and gives up by
+    /// returning false, e.g. on a timeout, which hands `val` back.
+    pub fn push_blocking(&self, mut val: T, mut wait: impl FnMut() -> bool) -> Result<(), T> {
+        loop {
+            match self.push(val) {
+                Ok(()) => return Ok(()),
+                Err(back) if wait() => val = back,
+                Err(back) => return Err(back),
+            }
+        }
+    }
+
+    /// Pop the value at the front of the queue, if there is one.
+    pub fn pop(&self) -> Option<T> {
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            let node = self.node(head);
+            let seq = node.seq.load(Ordering::Acquire);
+            match seq.wrapping_sub(head.wrapping_mul(2) | 1) as isize {
+                0 => match self.head.compare_exchange_weak(
+                    head,
+                    head.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        let val = unsafe { (*node.value.get()).assume_init_read() };
+                        let next = head.wrapping_add(self.nodes.len());
+                        node.seq.store(next.wrapping_mul(2), Ordering::Release);
+                        return Some(val);
+                    }
+                    Err(current) => head = current,
+                },
+                // Nothing has been pushed at this position yet.
+                lag if lag < 0 => return None,
+                _ => head = self.head.load(Ordering::Relaxed),
+            }
+        }
+    }
+
+    /// The number of values in the queue, which may be stale by the time it is used.
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        self.tail
+            .load(Ordering::Acquire)
+            .wrapping_sub(head)
+            .min(self.nodes.len())
+    }
+
+    /// Whether the queue holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for Queue<'_, T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
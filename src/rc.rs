@@ -0,0 +1,145 @@
+//! Reference counted values in an arena, with weak references to them.
+
+use core::{
+    cell::{Cell, UnsafeCell},
+    fmt,
+    mem::MaybeUninit,
+    ops::Deref,
+    ptr,
+};
+
+use crate::DynArena;
+
+/// A value and the number of [`ArenaRc`]s to it.
+///
+/// Its memory belongs to the arena and outlives the value, which is what lets
+/// an [`ArenaWeak`] see that the value is gone without a count of its own. The
+/// value is only initialized while the count is above zero.
+struct RcBox<T> {
+    strong: Cell<usize>,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// A shared pointer to a value in an arena, which is dropped when the last
+/// `ArenaRc` to it is, rather than when the arena is reset.
+///
+/// The memory of the value is only reclaimed when the arena is reset.
+pub struct ArenaRc<'a, T> {
+    inner: &'a RcBox<T>,
+}
+
+/// A pointer to the value of an [`ArenaRc`] that does not keep it alive, e.g.
+/// for a cache or a list of observers.
+///
+/// Upgrading it gives an `ArenaRc` for as long as one is left, and None once
+/// the value has been dropped. It never dangles, since the arena outlives it.
+pub struct ArenaWeak<'a, T> {
+    inner: &'a RcBox<T>,
+}
+
+impl<'a, T> ArenaRc<'a, T> {
+    /// acquire `val` from `arena` behind a new reference count.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn new(arena: &'a DynArena, val: T) -> Option<Self> {
+        let inner = arena.acquire(RcBox {
+            strong: Cell::new(1),
+            value: UnsafeCell::new(MaybeUninit::new(val)),
+        })?;
+        Some(ArenaRc { inner })
+    }
+
+    /// acquire a value built by `f` from a weak reference to itself, e.g. a tree
+    /// node whose children point back at it. Upgrading the weak reference gives
+    /// None until `f` has returned.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn new_cyclic(arena: &'a DynArena, f: impl FnOnce(&ArenaWeak<'a, T>) -> T) -> Option<Self> {
+        let inner = arena.acquire(RcBox {
+            strong: Cell::new(0),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        })?;
+        let val = f(&ArenaWeak { inner });
+        // Nothing can read the value before the count is raised, so writing it
+        // through the shared borrow cannot race a reader.
+        unsafe { (*inner.value.get()).write(val) };
+        inner.strong.set(1);
+        Some(ArenaRc { inner })
+    }
+
+    /// A weak reference to the value of `this`.
+    pub fn downgrade(this: &Self) -> ArenaWeak<'a, T> {
+        ArenaWeak { inner: this.inner }
+    }
+
+    /// The number of `ArenaRc`s to the value of `this`.
+    pub fn strong_count(this: &Self) -> usize {
+        this.inner.strong.get()
+    }
+
+    /// Whether `this` and `other` point to the same value.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        ptr::eq(this.inner, other.inner)
+    }
+}
+
+impl<T> Clone for ArenaRc<'_, T> {
+    fn clone(&self) -> Self {
+        self.inner.strong.set(self.inner.strong.get() + 1);
+        ArenaRc { inner: self.inner }
+    }
+}
+
+impl<T> Deref for ArenaRc<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // The value is only dropped once no ArenaRc to it is left.
+        unsafe { (*self.inner.value.get()).assume_init_ref() }
+    }
+}
+
+impl<T> Drop for ArenaRc<'_, T> {
+    fn drop(&mut self) {
+        let strong = self.inner.strong.get() - 1;
+        self.inner.strong.set(strong);
+        if strong == 0 {
+            unsafe { (*self.inner.value.get()).assume_init_drop() };
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArenaRc<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<'a, T> ArenaWeak<'a, T> {
+    /// An `ArenaRc` to the value, or None if it has been dropped.
+    pub fn upgrade(&self) -> Option<ArenaRc<'a, T>> {
+        let strong = self.inner.strong.get();
+        if strong == 0 {
+            return None;
+        }
+        self.inner.strong.set(strong + 1);
+        Some(ArenaRc { inner: self.inner })
+    }
+
+    /// The number of `ArenaRc`s to the value, zero once it has been dropped.
+    pub fn strong_count(&self) -> usize {
+        self.inner.strong.get()
+    }
+}
+
+impl<T> Clone for ArenaWeak<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ArenaWeak<'_, T> {}
+
+impl<T> fmt::Debug for ArenaWeak<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("(ArenaWeak)")
+    }
+}
@@ -0,0 +1,170 @@
+//! Recording of the allocations an arena makes, so a field failure can be replayed in tests.
+
+use core::{
+    any::type_name,
+    cell::UnsafeCell,
+    fmt,
+    mem::{align_of, size_of, MaybeUninit},
+    panic::Location,
+};
+
+use crate::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arena, DynArena,
+};
+
+/// One allocation attempt made by an arena.
+#[derive(Clone, Copy, Debug)]
+pub struct Record {
+    /// The name of the allocated type.
+    pub type_name: &'static str,
+    /// The size of the allocation in bytes.
+    pub size: usize,
+    /// The alignment of the allocation in bytes.
+    pub align: usize,
+    /// Where the allocation was made.
+    pub location: &'static Location<'static>,
+    /// Whether the arena had room for the allocation.
+    pub succeeded: bool,
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} size={} align={} at {} {}",
+            self.type_name,
+            self.size,
+            self.align,
+            self.location,
+            if self.succeeded { "ok" } else { "failed" }
+        )
+    }
+}
+
+/// Somewhere an arena can send the [`Record`] of each allocation it makes.
+pub trait RecordSink: Sync {
+    fn record(&self, record: Record);
+}
+
+/// A [`RecordSink`] that keeps the first N records.
+pub struct Recorder<const N: usize> {
+    len: AtomicUsize,
+    records: [UnsafeCell<MaybeUninit<Record>>; N],
+    written: [AtomicBool; N],
+}
+
+unsafe impl<const N: usize> Sync for Recorder<N> {}
+
+impl<const N: usize> Default for Recorder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Recorder<N> {
+    /// Create a new recorder with room for N records.
+    #[must_use]
+    pub const fn new() -> Self {
+        Recorder {
+            len: AtomicUsize::new(0),
+            records: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            written: [const { AtomicBool::new(false) }; N],
+        }
+    }
+
+    /// The number of allocations recorded, including any that did not fit.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Whether no allocations have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The records kept so far, in the order they were made.
+    pub fn records(&self) -> impl Iterator<Item = Record> + '_ {
+        self.written
+            .iter()
+            .zip(&self.records)
+            .take_while(|(written, _)| written.load(Ordering::Acquire))
+            .map(|(_, record)| unsafe { (*record.get()).assume_init() })
+    }
+
+    /// Forget every record.
+    pub fn clear(&mut self) {
+        *self.len.get_mut() = 0;
+        for written in &mut self.written {
+            *written.get_mut() = false;
+        }
+    }
+
+    /// Write every record, one per line.
+    pub fn dump(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        for (i, record) in self.records().enumerate() {
+            writeln!(w, "{i}: {record}")?;
+        }
+        Ok(())
+    }
+
+    /// Make allocations with the same sizes and alignments as the records, in order, in `arena`.
+    /// Returns the index of the first one that did not fit.
+    pub fn replay(&self, arena: &DynArena) -> Result<(), usize> {
+        for (i, record) in self.records().enumerate() {
+            arena.reserve(record.size, record.align).ok_or(i)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> RecordSink for Recorder<N> {
+    fn record(&self, record: Record) {
+        let i = self.len.fetch_add(1, Ordering::AcqRel);
+        if i >= N {
+            return;
+        }
+        unsafe { (*self.records[i].get()).write(record) };
+        self.written[i].store(true, Ordering::Release);
+    }
+}
+
+impl<const SIZE: usize> Arena<SIZE> {
+    /// Create a new arena with a fixed size buffer of SIZE bytes that sends
+    /// a record of each allocation it makes to `recorder`.
+    #[must_use]
+    pub const fn with_recorder(recorder: &'static dyn RecordSink) -> Self {
+        let mut arena = Self::new();
+        arena.0.recorder = Some(recorder);
+        arena
+    }
+}
+
+impl DynArena {
+    /// Send the record of an allocation of a T to the recorder, if any.
+    #[track_caller]
+    pub(crate) fn record<T>(&self, succeeded: bool) {
+        self.record_layout(type_name::<T>(), size_of::<T>(), align_of::<T>(), succeeded);
+    }
+
+    /// Send the record of an allocation with the given layout to the recorder, if any.
+    #[track_caller]
+    pub(crate) fn record_layout(
+        &self,
+        type_name: &'static str,
+        size: usize,
+        align: usize,
+        succeeded: bool,
+    ) {
+        let Some(recorder) = self.recorder else {
+            return;
+        };
+        recorder.record(Record {
+            type_name,
+            size,
+            align,
+            location: Location::caller(),
+            succeeded,
+        });
+    }
+}
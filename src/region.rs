@@ -0,0 +1,69 @@
+//! Arenas that keep their values in memory borrowed from the caller.
+
+use core::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{sealed::Storage, DropSlot, DynArena};
+
+/// Borrowed storage, held as raw slices so that the arena type stays `'static`.
+pub(crate) struct Region {
+    pub(crate) store: *mut [u8],
+    pub(crate) drop_queue: *mut [DropSlot],
+}
+
+impl Storage for Region {
+    fn store(&self) -> *mut [u8] {
+        self.store
+    }
+
+    fn drop_queue(&self) -> *mut [DropSlot] {
+        self.drop_queue
+    }
+}
+
+/// An arena backed by a byte buffer and drop queue that the caller provides,
+/// e.g. a linker placed region or a buffer whose size is only known at runtime.
+///
+/// Derefs to a [`DynArena`], which has all of its methods.
+pub struct RegionArena<'a> {
+    pub(crate) arena: DynArena<Region>,
+    _region: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> RegionArena<'a> {
+    /// Create a new arena that places values in `store` and can hold the
+    /// destructors of up to `drop_queue.len()` of them.
+    pub fn new(store: &'a mut [u8], drop_queue: &'a mut [DropSlot]) -> Self {
+        Self::with_region(Region { store, drop_queue })
+    }
+
+    /// Create a new arena over `region`, which must stay valid for 'a.
+    pub(crate) fn with_region(region: Region) -> Self {
+        RegionArena {
+            arena: DynArena::with_storage(region),
+            _region: PhantomData,
+        }
+    }
+}
+
+impl Deref for RegionArena<'_> {
+    type Target = DynArena;
+
+    fn deref(&self) -> &DynArena {
+        &self.arena
+    }
+}
+
+impl DerefMut for RegionArena<'_> {
+    fn deref_mut(&mut self) -> &mut DynArena {
+        &mut self.arena
+    }
+}
+
+impl Drop for RegionArena<'_> {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
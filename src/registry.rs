@@ -0,0 +1,79 @@
+//! A registry of named `static` arenas, for reporting on all of them at once.
+
+use core::{cell::UnsafeCell, fmt, mem::MaybeUninit};
+
+use crate::{
+    atomic::{AtomicU8, Ordering},
+    DynArena,
+};
+
+/// The number of arenas that can be registered.
+pub const REGISTRY_SLOTS: usize = 8;
+
+const FREE: u8 = 0;
+const CLAIMED: u8 = 1;
+const READY: u8 = 2;
+
+/// A slot of the registry, which holds an arena and its name once it is ready.
+struct Entry {
+    state: AtomicU8,
+    arena: UnsafeCell<MaybeUninit<(&'static str, &'static DynArena)>>,
+}
+
+// An entry is only written by the context that claimed it, before it is marked
+// ready, and only read after.
+unsafe impl Sync for Entry {}
+
+static REGISTRY: [Entry; REGISTRY_SLOTS] = [const {
+    Entry {
+        state: AtomicU8::new(FREE),
+        arena: UnsafeCell::new(MaybeUninit::uninit()),
+    }
+}; REGISTRY_SLOTS];
+
+impl DynArena {
+    /// Add the arena to the registry under `name`, so that [`report_all`] and
+    /// [`registered`] include it. Returns false if the registry is full.
+    pub fn register(&'static self, name: &'static str) -> bool {
+        for entry in &REGISTRY {
+            if entry
+                .state
+                .compare_exchange(FREE, CLAIMED, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                unsafe { (*entry.arena.get()).write((name, self)) };
+                entry.state.store(READY, Ordering::Release);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// The registered arenas with their names, in the order they were registered in.
+pub fn registered() -> impl Iterator<Item = (&'static str, &'static DynArena)> {
+    REGISTRY
+        .iter()
+        .filter(|entry| entry.state.load(Ordering::Acquire) == READY)
+        .map(|entry| unsafe { (*entry.arena.get()).assume_init() })
+}
+
+/// Write the stats of every registered arena to `w`, one line each with its name,
+/// capacity, bytes used and the share of the capacity they are, padding, values
+/// and generation, e.g. to find which of a system's arenas is filling up.
+pub fn report_all(w: &mut impl fmt::Write) -> fmt::Result {
+    writeln!(
+        w,
+        "arena            capacity     used     pad  values  generation"
+    )?;
+    for (name, arena) in registered() {
+        let stats = arena.stats();
+        let percent = (stats.used * 100).checked_div(stats.capacity).unwrap_or(0);
+        writeln!(
+            w,
+            "{name:<16} {:>8} {:>8} {percent:>3}% {:>5} {:>7} {:>11}",
+            stats.capacity, stats.used, stats.padding, stats.values, stats.generation
+        )?;
+    }
+    Ok(())
+}
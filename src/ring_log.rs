@@ -0,0 +1,210 @@
+//! A circular log of formatted records kept in an arena.
+
+use core::{fmt, str};
+
+use crate::DynArena;
+
+/// The size of the length that comes before every record.
+const HEADER: usize = 2;
+
+/// A length that marks the rest of the buffer as unused, the next record being
+/// at its start.
+const WRAP: u16 = u16::MAX;
+
+/// A log of text records in a fixed-size buffer acquired from an arena, which
+/// drops its oldest records to make room for new ones, e.g. to keep the last
+/// events before a fault in a static arena that a debugger or the next boot can
+/// find and dump.
+///
+/// Each record is kept whole after a two-byte length, and one that does not fit
+/// before the end of the buffer starts over at its beginning. A record longer
+/// than the buffer is cut short.
+pub struct RingLog<'a> {
+    buf: &'a mut [u8],
+    // The offset of the oldest record, never a wrap marker, and the offset the
+    // next one is written at.
+    head: usize,
+    tail: usize,
+    len: usize,
+}
+
+impl<'a> RingLog<'a> {
+    /// acquire an empty log with a buffer of `capacity` bytes from `arena`.
+    /// Returns None if the capacity is too small to hold a record or the arena is out of space.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn new(arena: &'a DynArena, capacity: usize) -> Option<Self> {
+        if capacity <= HEADER {
+            return None;
+        }
+        let buf = arena.acquire_slice_try_fill_with(capacity, |_| Some(0))?;
+        Some(RingLog {
+            buf,
+            head: 0,
+            tail: 0,
+            len: 0,
+        })
+    }
+
+    /// The number of records in the log.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the log holds no records.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The size of the buffer of the log in bytes, two of which go to every record.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// The longest record the log can hold.
+    fn max_record(&self) -> usize {
+        (self.buf.len() - HEADER).min(usize::from(WRAP) - 1)
+    }
+
+    /// Add a record formatted from `args`, e.g. with `format_args!`, dropping the
+    /// oldest records to make room for it.
+    pub fn log(&mut self, args: fmt::Arguments<'_>) {
+        let mut counter = Counter(0);
+        let _ = fmt::write(&mut counter, args);
+        self.push(counter.0, |writer| {
+            let _ = fmt::write(writer, args);
+        });
+    }
+
+    /// Add `record`, dropping the oldest records to make room for it.
+    pub fn log_str(&mut self, record: &str) {
+        self.push(record.len(), |writer| {
+            let _ = fmt::Write::write_str(writer, record);
+        });
+    }
+
+    /// Drop every record.
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.tail = 0;
+        self.len = 0;
+    }
+
+    /// The records, from the oldest to the newest.
+    pub fn iter(&self) -> impl Iterator<Item = &str> + '_ {
+        let mut at = self.head;
+        (0..self.len).map(move |_| {
+            at = self.skip_wrap(at);
+            let len = self.record_len(at);
+            let record = &self.buf[at + HEADER..][..len];
+            at += HEADER + len;
+            unsafe { str::from_utf8_unchecked(record) }
+        })
+    }
+
+    /// Add a record of up to `len` bytes written by `write`.
+    fn push(&mut self, len: usize, write: impl FnOnce(&mut Truncating<'_>)) {
+        let len = len.min(self.max_record());
+        let start = self.make_room(len);
+        let mut writer = Truncating {
+            buf: &mut self.buf[start..start + len],
+            len: 0,
+        };
+        write(&mut writer);
+        let written = writer.len;
+        self.finish(start, written);
+    }
+
+    /// Make room for a record of `len` bytes at the tail, returning the offset
+    /// its contents go at.
+    fn make_room(&mut self, len: usize) -> usize {
+        if self.len == 0 {
+            self.clear();
+        }
+        let size = HEADER + len;
+        if self.tail + size > self.buf.len() {
+            while self.len > 0 && self.head >= self.tail {
+                self.drop_oldest();
+            }
+            if self.buf.len() - self.tail >= HEADER {
+                self.buf[self.tail..][..HEADER].copy_from_slice(&WRAP.to_le_bytes());
+            }
+            self.tail = 0;
+        }
+        while self.len > 0 && self.head >= self.tail && self.head < self.tail + size {
+            self.drop_oldest();
+        }
+        self.tail + HEADER
+    }
+
+    /// Write the length of the record of `len` bytes whose contents are at `start`.
+    fn finish(&mut self, start: usize, len: usize) {
+        let header = u16::try_from(len).unwrap_or(WRAP - 1);
+        self.buf[start - HEADER..start].copy_from_slice(&header.to_le_bytes());
+        if self.len == 0 {
+            self.head = start - HEADER;
+        }
+        self.tail = start + len;
+        self.len += 1;
+    }
+
+    /// Drop the oldest record, leaving the head at the record after it.
+    fn drop_oldest(&mut self) {
+        self.head += HEADER + self.record_len(self.head);
+        self.len -= 1;
+        if self.len > 0 {
+            self.head = self.skip_wrap(self.head);
+        }
+    }
+
+    /// The offset of the record at `at`, or at the start of the buffer if `at` is
+    /// past the last record before the end.
+    fn skip_wrap(&self, at: usize) -> usize {
+        if self.buf.len() - at < HEADER || self.record_len(at) == usize::from(WRAP) {
+            0
+        } else {
+            at
+        }
+    }
+
+    fn record_len(&self, at: usize) -> usize {
+        usize::from(u16::from_le_bytes([self.buf[at], self.buf[at + 1]]))
+    }
+}
+
+impl fmt::Debug for RingLog<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// A fmt::Write that only counts the bytes written to it.
+struct Counter(usize);
+
+impl fmt::Write for Counter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// A fmt::Write into a buffer that keeps what fits, up to a char boundary.
+struct Truncating<'b> {
+    buf: &'b mut [u8],
+    len: usize,
+}
+
+impl fmt::Write for Truncating<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let room = self.buf.len() - self.len;
+        let mut fits = s.len().min(room);
+        while !s.is_char_boundary(fits) {
+            fits -= 1;
+        }
+        self.buf[self.len..][..fits].copy_from_slice(&s.as_bytes()[..fits]);
+        self.len += fits;
+        if fits < s.len() {
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+//! Scopes whose acquisitions are rolled back when they end.
+
+use core::marker::PhantomData;
+
+use crate::{atomic::Ordering, DynArena, Stats};
+
+impl DynArena {
+    /// Call `f` with a [`Scope`] of the arena, then drop everything acquired in it
+    /// and hand its room back, keeping what was acquired before, e.g. for the
+    /// temporaries of one request handled by a long lived server arena.
+    ///
+    /// The scope ends with `f`, so no reference to what was acquired in it can be
+    /// returned or stashed away. The rollback happens even if `f` panics.
+    pub fn scope<'env, R>(&'env mut self, f: impl for<'s> FnOnce(&mut Scope<'s, 'env>) -> R) -> R {
+        self.rewinding(f)
+    }
+
+    /// Call `f` with a new scope of the arena, rolling the arena back to where it
+    /// is now once `f` returns or panics.
+    fn rewinding<'env, R>(&self, f: impl for<'s> FnOnce(&mut Scope<'s, 'env>) -> R) -> R {
+        let _rewind = Rewind {
+            arena: self,
+            store: self.next_free_store_spot.load(Ordering::Acquire),
+            drops: self.next_free_drop_spot.load(Ordering::Acquire),
+            padding: self.padding.load(Ordering::Relaxed),
+        };
+        f(&mut Scope {
+            arena: self,
+            scope: PhantomData,
+            env: PhantomData,
+        })
+    }
+}
+
+/// A stretch of an arena's life whose acquisitions are rolled back when it ends,
+/// given out by [`DynArena::scope`].
+///
+/// Scopes nest through [`Scope::scope`]. Values acquired in a scope live for all
+/// of it, inner scopes included, and may only borrow from what outlives the scope,
+/// `'env`, since they are dropped when it ends. The lifetime `'s` of each scope is
+/// its own, so nothing from an inner scope can end up in one that outlives it:
+///
+/// ```compile_fail
+/// # use arena_alloc::Arena;
+/// # use core::cell::Cell;
+/// let mut arena = Arena::<64>::new();
+/// arena.scope(|outer| {
+///     let kept = outer.acquire(Cell::new(&0u32)).unwrap();
+///     outer.scope(|inner| {
+///         kept.set(inner.acquire(1u32).unwrap());
+///     });
+/// });
+/// ```
+///
+/// nor be returned out of it:
+///
+/// ```compile_fail
+/// # use arena_alloc::Arena;
+/// let mut arena = Arena::<64>::new();
+/// let escaped = arena.scope(|scope| scope.acquire(1u32).unwrap());
+/// ```
+///
+/// and an outer scope can not acquire while an inner one is open, which would
+/// have its values rolled back with the inner scope's:
+///
+/// ```compile_fail
+/// # use arena_alloc::Arena;
+/// let mut arena = Arena::<64>::new();
+/// arena.scope(|outer| {
+///     outer.scope(|_inner| {
+///         outer.acquire(1u32);
+///     });
+/// });
+/// ```
+///
+/// Nor can a value borrow from a local of the scope, or of a scope nested in it,
+/// which is gone by the time the value is dropped as the scope ends:
+///
+/// ```compile_fail
+/// # use arena_alloc::Arena;
+/// let mut arena = Arena::<64>::new();
+/// arena.scope(|outer| {
+///     outer.scope(|inner| {
+///         let local = 1u32;
+///         inner.acquire(&local);
+///     });
+/// });
+/// ```
+pub struct Scope<'s, 'env: 's> {
+    arena: &'s DynArena,
+    // Invariant in both, so that neither can be stretched or shrunk to fit another
+    // scope's.
+    scope: PhantomData<&'s mut &'s ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'s, 'env> Scope<'s, 'env> {
+    /// acquire a reference to a value of type T that is initialized with the given
+    /// value, which lives until the scope ends.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire<T: 'env>(&self, val: T) -> Option<&'s T> {
+        self.arena.acquire(val)
+    }
+
+    /// acquire a reference to a value of type T that is initialized with it's
+    /// default value, which lives until the scope ends.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_default<T: Default + 'env>(&self) -> Option<&'s T> {
+        self.arena.acquire_default()
+    }
+
+    /// acquire a reference to a value of type T that is dropped with `parent`, see
+    /// [`DynArena::acquire_child_of`], which lives until the scope ends.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_child_of<P: ?Sized, T: 'env>(&self, parent: &P, val: T) -> Option<&'s T> {
+        self.arena.acquire_child_of(parent, val)
+    }
+
+    /// acquire a buffer holding a copy of `bytes`, which lives until the scope ends.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_bytes_copy(&self, bytes: &[u8]) -> Option<&'s mut [u8]> {
+        self.arena.acquire_bytes_copy(bytes)
+    }
+
+    /// Register `f` to be called when the scope ends, see [`DynArena::on_drop`].
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn on_drop<F: FnOnce() + 'static>(&self, f: F) -> Result<(), F> {
+        self.arena.on_drop(f)
+    }
+
+    /// Take a snapshot of how much of the arena is in use.
+    pub fn stats(&self) -> Stats {
+        self.arena.stats()
+    }
+
+    /// acquire a string holding every part, one after another, which lives until
+    /// the scope ends.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn concat_strs(&self, parts: &[&str]) -> Option<&'s str> {
+        self.arena.concat_strs(parts)
+    }
+
+    /// Call `f` with a scope nested in this one, rolling back what is acquired in it
+    /// when it ends while keeping what this scope acquired before.
+    pub fn scope<'a, R>(&'a mut self, f: impl for<'i> FnOnce(&mut Scope<'i, 'a>) -> R) -> R {
+        self.arena.rewinding(f)
+    }
+}
+
+/// Rolls an arena back to where a scope started when the scope ends.
+struct Rewind<'a> {
+    arena: &'a DynArena,
+    store: usize,
+    drops: usize,
+    padding: usize,
+}
+
+impl Drop for Rewind<'_> {
+    fn drop(&mut self) {
+        // The scope's borrow of the arena has ended, so nothing acquired in it is
+        // still referenced, and nothing acquired before it is past where it started.
+        unsafe { self.arena.rewind_to(self.store, self.drops, self.padding) };
+    }
+}
@@ -0,0 +1,99 @@
+//! Scratch space for real-time callbacks, reserved once and reused every cycle.
+
+use core::{
+    cell::Cell,
+    marker::PhantomData,
+    mem::{align_of, size_of, MaybeUninit},
+    slice,
+};
+
+use crate::{align_place, DynArena};
+
+/// A block acquired from an arena once, e.g. when an audio stream is set up, and
+/// bumped through by each processing cycle without touching the arena again.
+///
+/// Every cycle starts at the beginning of the block, so what one cycle acquires
+/// is gone by the next. Acquiring from a cycle only updates a plain cursor: no
+/// atomics, no locks and no drop queue, so it is safe to do in an audio callback
+/// that must never contend with other threads.
+pub struct ScratchRegion<'a> {
+    buf: &'a mut [MaybeUninit<u8>],
+}
+
+impl<'a> ScratchRegion<'a> {
+    /// acquire a region of `size` bytes from `arena`.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn new(arena: &'a DynArena, size: usize) -> Option<Self> {
+        let buf = arena.acquire_slice_uninit::<u8>(size)?;
+        Some(ScratchRegion { buf })
+    }
+
+    /// The size of the region in bytes.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Start a processing cycle, with all of the region free again.
+    pub fn cycle(&mut self) -> Scratch<'_> {
+        Scratch {
+            start: self.buf.as_mut_ptr().cast(),
+            capacity: self.buf.len(),
+            cursor: Cell::new(0),
+            _region: PhantomData,
+        }
+    }
+}
+
+/// One processing cycle of a [`ScratchRegion`], which values are acquired from
+/// until it is dropped at the end of the cycle.
+///
+/// Only values that need no dropping can be acquired, since nothing is dropped
+/// when the cycle ends.
+pub struct Scratch<'r> {
+    start: *mut u8,
+    capacity: usize,
+    cursor: Cell<usize>,
+    _region: PhantomData<&'r mut [u8]>,
+}
+
+impl Scratch<'_> {
+    /// The number of bytes acquired in this cycle, padding included.
+    pub fn used(&self) -> usize {
+        self.cursor.get()
+    }
+
+    /// acquire a value initialized with `val`.
+    // Every call bumps the cursor past a disjoint part of the region, so handing
+    // out a mutable reference to it from a shared borrow of the cycle is sound.
+    #[allow(clippy::mut_from_ref)]
+    pub fn acquire<T: Copy>(&self, val: T) -> Option<&mut T> {
+        let ptr = self.bump::<T>(1)?;
+        unsafe { ptr.write(val) };
+        Some(unsafe { &mut *ptr })
+    }
+
+    /// acquire a slice of `len` values, each a copy of `val`, e.g. a block of
+    /// samples to mix into.
+    #[allow(clippy::mut_from_ref)]
+    pub fn acquire_slice<T: Copy>(&self, len: usize, val: T) -> Option<&mut [T]> {
+        let start = self.bump::<T>(len)?;
+        for i in 0..len {
+            unsafe { start.add(i).write(val) };
+        }
+        Some(unsafe { slice::from_raw_parts_mut(start, len) })
+    }
+
+    /// Move the cursor past room for `len` values of type T, returning where they go.
+    fn bump<T>(&self, len: usize) -> Option<*mut T> {
+        let size = size_of::<T>().checked_mul(len)?;
+        let (place, end) = align_place(
+            self.start.addr(),
+            self.cursor.get(),
+            size,
+            align_of::<T>(),
+            self.capacity,
+        )?;
+        self.cursor.set(end);
+        Some(unsafe { self.start.add(place).cast() })
+    }
+}
@@ -0,0 +1,63 @@
+//! Values whose memory is zeroed once they are dropped, for keys and credentials
+//! that should not linger in RAM after use.
+
+use core::{mem::MaybeUninit, slice};
+
+use zeroize::Zeroize;
+
+use crate::{annotate, DynArena, TypeInfo};
+
+/// Zero the `len` bytes at `ptr` in a way the compiler cannot optimize away.
+///
+/// # Safety
+/// `ptr` must be valid for writes of `len` bytes.
+unsafe fn zero(ptr: *mut u8, len: usize) {
+    unsafe { slice::from_raw_parts_mut(ptr.cast::<MaybeUninit<u8>>(), len) }.zeroize();
+}
+
+impl TypeInfo {
+    /// The info for a secret of type T, which is zeroed after it is dropped.
+    fn of_secret<T>() -> &'static TypeInfo {
+        const {
+            &TypeInfo {
+                drop_func: |ptr: *mut u8| unsafe {
+                    ptr.cast::<T>().drop_in_place();
+                    zero(ptr, core::mem::size_of::<T>());
+                },
+                needs_drop: true,
+                ..*TypeInfo::of::<T>()
+            }
+        }
+    }
+}
+
+impl DynArena {
+    /// acquire a reference to a value of type T that is initialized with the given
+    /// value, and whose memory is zeroed once it is dropped, e.g. a session key.
+    ///
+    /// Only the value's own bytes are zeroed; anything it owns elsewhere should
+    /// zero itself when dropped.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_secret<T>(&self, val: T) -> Option<&T> {
+        let mut slot = self.claim_drop_slots(1)?;
+        let (place, ptr) = self.get_ptr_place::<T>()?;
+        let val = ptr.write(val);
+        slot.fill(place, TypeInfo::of_secret::<T>());
+        Some(&*val)
+    }
+
+    /// Reset the arena, then zero its whole backing store, so that nothing acquired
+    /// from it lingers, including what scopes that have ended left behind.
+    ///
+    /// Takes time in proportion to the capacity of the arena.
+    pub fn zeroize_all(&mut self) {
+        self.reset();
+        let store = self.store().cast::<u8>();
+        let capacity = self.capacity();
+        annotate::expose(store, capacity);
+        unsafe { zero(store, capacity) };
+        if self.annotations_started() {
+            annotate::rewind(store, 0, capacity);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Slices of values acquired from an arena.
+
+use core::{
+    mem::{align_of, needs_drop, size_of, MaybeUninit},
+    ptr, slice,
+    str::Utf8Error,
+};
+
+use crate::{slice_header, DropClaim, DynArena, Reservation, TypeInfo};
+
+/// Drops the elements of a slice that have been initialized so far if
+/// initializing the rest panics.
+pub(crate) struct Partial<T> {
+    pub(crate) start: *mut T,
+    pub(crate) len: usize,
+}
+
+impl<T> Drop for Partial<T> {
+    fn drop(&mut self) {
+        unsafe { ptr::slice_from_raw_parts_mut(self.start, self.len).drop_in_place() };
+    }
+}
+
+impl DynArena {
+    /// Reserve room for `len` values of type T, after a length header if T needs dropping.
+    /// Returns the offset of the reservation and a pointer to its first element.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub(crate) fn reserve_slice<T>(&self, len: usize) -> Option<(usize, *mut T)> {
+        self.reserve_slice_aligned(len, align_of::<T>())
+    }
+
+    /// Like `reserve_slice`, but with the first element aligned to at least `align`,
+    /// which must be a power of two. The length header, if any, is right before it,
+    /// and the offset returned is that of the header.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub(crate) fn reserve_slice_aligned<T>(
+        &self,
+        len: usize,
+        align: usize,
+    ) -> Option<(usize, *mut T)> {
+        let align = align.max(align_of::<T>());
+        let (header, front, align) = if needs_drop::<T>() {
+            let align = align.max(align_of::<usize>());
+            (
+                slice_header::<T>(),
+                slice_header::<T>().next_multiple_of(align),
+                align,
+            )
+        } else {
+            (0, 0, align)
+        };
+        let size = size_of::<T>().checked_mul(len)?.checked_add(front)?;
+        let place = self.reserve(size, align);
+        #[cfg(feature = "record")]
+        self.record_layout(core::any::type_name::<[T]>(), size, align, place.is_some());
+        let place = place? + front - header;
+
+        let start = unsafe { self.store().cast::<u8>().add(place + header) };
+        Some((place, start.cast()))
+    }
+
+    /// Claim an entry in the drop queue for each of `count` slices of T, if they take one.
+    pub(crate) fn claim_slice_drop_slots<T>(&self, count: usize) -> Option<DropClaim<'_>> {
+        self.claim_drop_slots(if needs_drop::<T>() { count } else { 0 })
+    }
+
+    /// Fill the next entry of `slot`, claimed by `claim_slice_drop_slots`, with the
+    /// destructor of the `len` values at `place`, reserved by `reserve_slice`.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub(crate) fn add_slice_to_drop_queue<T>(
+        &self,
+        slot: &mut DropClaim<'_>,
+        place: usize,
+        len: usize,
+    ) {
+        if !needs_drop::<T>() {
+            return;
+        }
+        unsafe {
+            self.store()
+                .cast::<u8>()
+                .add(place)
+                .cast::<usize>()
+                .write(len)
+        };
+        slot.fill(place, TypeInfo::of_slice::<T>());
+    }
+
+    /// acquire a slice of `len` values of type T, each initialized with the result
+    /// of calling `f` with its index.
+    /// If `f` panics, the values it has already returned are dropped.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_slice_fill_with<T>(
+        &self,
+        len: usize,
+        mut f: impl FnMut(usize) -> T,
+    ) -> Option<&[T]> {
+        self.acquire_slice_try_fill_with(len, |i| Some(f(i)))
+            .map(|slice| &*slice)
+    }
+
+    /// Like [`DynArena::acquire_slice_fill_with`], but gives up as soon as `f` returns None,
+    /// dropping the values it has already returned and handing the room back if nothing
+    /// has been acquired since.
+    #[cfg_attr(feature = "record", track_caller)]
+    // Every call reserves a disjoint region of the backing store, so handing out
+    // a mutable reference to it from a shared borrow of the arena is sound.
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn acquire_slice_try_fill_with<T>(
+        &self,
+        len: usize,
+        f: impl FnMut(usize) -> Option<T>,
+    ) -> Option<&mut [T]> {
+        self.acquire_slice_aligned_try_fill_with(len, align_of::<T>(), f)
+    }
+
+    /// Like [`DynArena::acquire_slice_try_fill_with`], but with the first value aligned
+    /// to at least `align`, which must be a power of two.
+    #[cfg_attr(feature = "record", track_caller)]
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn acquire_slice_aligned_try_fill_with<T>(
+        &self,
+        len: usize,
+        align: usize,
+        mut f: impl FnMut(usize) -> Option<T>,
+    ) -> Option<&mut [T]> {
+        let mut slot = self.claim_slice_drop_slots::<T>(1)?;
+        let (place, start) = self.reserve_slice_aligned::<T>(len, align)?;
+        // Handed back if `f` fails or panics, once the values it returned are dropped.
+        let header = if needs_drop::<T>() { slice_header::<T>() } else { 0 };
+        let reservation = Reservation::new(self, place, header + size_of::<T>() * len);
+
+        let mut partial = Partial { start, len: 0 };
+        while partial.len < len {
+            let val = f(partial.len)?;
+            unsafe { start.add(partial.len).write(val) };
+            partial.len += 1;
+        }
+        core::mem::forget(partial);
+        reservation.keep();
+
+        self.add_slice_to_drop_queue::<T>(&mut slot, place, len);
+
+        Some(unsafe { slice::from_raw_parts_mut(start, len) })
+    }
+
+    /// acquire a slice of `len` uninitialized values of type T, to be initialized
+    /// in any order and then handed to [`DynArena::assume_init_slice`].
+    /// Until then the arena will not drop any of its values.
+    // Every call reserves a disjoint region of the backing store, so handing out
+    // a mutable reference to it from a shared borrow of the arena is sound.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_slice_uninit<T>(&self, len: usize) -> Option<&mut [MaybeUninit<T>]> {
+        let (_, start) = self.reserve_slice::<T>(len)?;
+        Some(unsafe { slice::from_raw_parts_mut(start.cast(), len) })
+    }
+
+    /// Declare every value of a slice acquired with [`DynArena::acquire_slice_uninit`]
+    /// initialized, so that the arena drops them when it is reset or dropped.
+    /// Returns None if the values need dropping and the drop queue is full, in which
+    /// case they are still the caller's to drop.
+    ///
+    /// # Safety
+    /// `slice` must be the whole of a slice acquired from this arena with
+    /// `acquire_slice_uninit` since its last reset, not yet passed to this
+    /// function, and every one of its values must be initialized.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub unsafe fn assume_init_slice<'a, T>(
+        &'a self,
+        slice: &'a mut [MaybeUninit<T>],
+    ) -> Option<&'a [T]> {
+        let start = slice.as_mut_ptr().cast::<T>();
+        let mut slot = self.claim_slice_drop_slots::<T>(1)?;
+        if needs_drop::<T>() {
+            let place = start.addr() - self.store().cast::<u8>().addr() - slice_header::<T>();
+            self.add_slice_to_drop_queue::<T>(&mut slot, place, slice.len());
+        }
+        Some(unsafe { slice::from_raw_parts(start, slice.len()) })
+    }
+
+    /// acquire a slice holding clones of the values of every part, one after another,
+    /// e.g. to assemble a protocol frame from a header and a payload.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn concat_slices<T: Clone>(&self, parts: &[&[T]]) -> Option<&[T]> {
+        let len = parts
+            .iter()
+            .try_fold(0usize, |len, part| len.checked_add(part.len()))?;
+        let mut values = parts.iter().flat_map(|part| part.iter());
+        self.acquire_slice_fill_with(len, |_| values.next().unwrap().clone())
+    }
+
+    /// acquire a string holding every part, one after another.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn concat_strs(&self, parts: &[&str]) -> Option<&str> {
+        let len = parts
+            .iter()
+            .try_fold(0usize, |len, part| len.checked_add(part.len()))?;
+        let mut bytes = parts.iter().flat_map(|part| part.bytes());
+        let bytes = self.acquire_slice_fill_with(len, |_| bytes.next().unwrap())?;
+        Some(unsafe { core::str::from_utf8_unchecked(bytes) })
+    }
+
+    /// acquire a copy of `bytes` and check that it is UTF-8.
+    /// If it is not, the error comes with the copied bytes so they can still be used.
+    /// Returns None if the arena has no room for the copy.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_str_from_utf8(&self, bytes: &[u8]) -> Option<Result<&str, (Utf8Error, &[u8])>> {
+        let bytes = self.acquire_slice_fill_with(bytes.len(), |i| bytes[i])?;
+        Some(core::str::from_utf8(bytes).map_err(|e| (e, bytes)))
+    }
+}
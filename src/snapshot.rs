@@ -0,0 +1,189 @@
+//! Captures of the allocations an arena holds, for tests to diff and catch
+//! allocation regressions with.
+
+use core::{any::type_name, fmt};
+
+use crate::{DynArena, Stats};
+
+/// The number of values of one type an arena held, and the bytes they took.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tally {
+    /// The name of the type.
+    pub type_name: &'static str,
+    /// The number of values of the type.
+    pub count: usize,
+    /// The size of the values in bytes, not counting padding or slice length headers.
+    pub bytes: usize,
+}
+
+impl Tally {
+    const NONE: Tally = Tally {
+        type_name: "",
+        count: 0,
+        bytes: 0,
+    };
+}
+
+/// The allocations an arena held at one moment, by type, taken with
+/// [`DynArena::snapshot`].
+///
+/// Keeps a [`Tally`] for the first N types found. Values of further types only
+/// count towards `stats`. Slices of types that need no dropping are not tallied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Snapshot<const N: usize = 16> {
+    /// How much of the arena was in use.
+    pub stats: Stats,
+    tallies: [Tally; N],
+    len: usize,
+}
+
+impl<const N: usize> Snapshot<N> {
+    /// The tally of every type found, in the order its first value was acquired.
+    pub fn tallies(&self) -> &[Tally] {
+        &self.tallies[..self.len]
+    }
+
+    /// The tally of values of type T, which counts none if there were none.
+    pub fn tally_of<T: ?Sized>(&self) -> Tally {
+        self.tally(type_name::<T>())
+    }
+
+    fn tally(&self, type_name: &'static str) -> Tally {
+        let found = self.tallies().iter().find(|t| t.type_name == type_name);
+        found.copied().unwrap_or(Tally {
+            type_name,
+            ..Tally::NONE
+        })
+    }
+
+    /// What changed between this snapshot and the `later` one, e.g. to assert
+    /// that handling a request allocates exactly N values of M bytes.
+    pub fn diff(&self, later: &Snapshot<N>) -> SnapshotDiff<N> {
+        SnapshotDiff {
+            before: *self,
+            after: *later,
+        }
+    }
+}
+
+/// The change in the number of values of one type and the bytes they take.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// The name of the type.
+    pub type_name: &'static str,
+    /// The number of values of the type acquired, less those dropped.
+    pub count: isize,
+    /// The change in the size of the values in bytes.
+    pub bytes: isize,
+}
+
+/// What changed between two snapshots of an arena, made with [`Snapshot::diff`].
+///
+/// Its `Display` lists every change, for the message of a failed assertion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnapshotDiff<const N: usize = 16> {
+    before: Snapshot<N>,
+    after: Snapshot<N>,
+}
+
+impl<const N: usize> SnapshotDiff<N> {
+    /// The number of values acquired, less those dropped.
+    pub fn allocations(&self) -> isize {
+        delta(self.before.stats.values, self.after.stats.values)
+    }
+
+    /// The change in the bytes of the arena in use, including padding and bytes
+    /// held by no value, such as byte buffers.
+    pub fn bytes(&self) -> isize {
+        delta(self.before.stats.used, self.after.stats.used)
+    }
+
+    /// Whether nothing was acquired or dropped, as far as the snapshots tell.
+    pub fn is_empty(&self) -> bool {
+        self.allocations() == 0 && self.bytes() == 0 && self.changes().next().is_none()
+    }
+
+    /// The change in values of type T.
+    pub fn change_of<T: ?Sized>(&self) -> Change {
+        self.change(type_name::<T>())
+    }
+
+    fn change(&self, type_name: &'static str) -> Change {
+        let (before, after) = (self.before.tally(type_name), self.after.tally(type_name));
+        Change {
+            type_name,
+            count: delta(before.count, after.count),
+            bytes: delta(before.bytes, after.bytes),
+        }
+    }
+
+    /// The change in every type that changed, those in the later snapshot first.
+    pub fn changes(&self) -> impl Iterator<Item = Change> + '_ {
+        let after = self.after.tallies().iter();
+        let gone = self.before.tallies().iter().filter(|t| {
+            !self
+                .after
+                .tallies()
+                .iter()
+                .any(|a| a.type_name == t.type_name)
+        });
+        after
+            .chain(gone)
+            .map(|t| self.change(t.type_name))
+            .filter(|c| c.count != 0 || c.bytes != 0)
+    }
+}
+
+impl<const N: usize> fmt::Display for SnapshotDiff<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:+} allocations, {:+} bytes",
+            self.allocations(),
+            self.bytes()
+        )?;
+        for change in self.changes() {
+            writeln!(
+                f,
+                "{:+} {} ({:+} bytes)",
+                change.count, change.type_name, change.bytes
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// `after - before`, for counts that can go down as well as up.
+fn delta(before: usize, after: usize) -> isize {
+    after.wrapping_sub(before) as isize
+}
+
+impl DynArena {
+    /// Capture the allocations the arena holds, to diff against a later capture in
+    /// tests. Takes time in proportion to the number of values in the arena.
+    pub fn snapshot<const N: usize>(&self) -> Snapshot<N> {
+        let mut snapshot = Snapshot {
+            stats: self.stats(),
+            tallies: [Tally::NONE; N],
+            len: 0,
+        };
+        self.for_each_allocation(|_, size, type_name| {
+            let len = snapshot.len;
+            let tally = match snapshot.tallies[..len]
+                .iter_mut()
+                .position(|t| t.type_name == type_name)
+            {
+                Some(i) => &mut snapshot.tallies[i],
+                None if len < N => {
+                    snapshot.len += 1;
+                    snapshot.tallies[len].type_name = type_name;
+                    &mut snapshot.tallies[len]
+                }
+                None => return,
+            };
+            tally.count += 1;
+            tally.bytes += size;
+        });
+        snapshot
+    }
+}
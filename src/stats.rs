@@ -0,0 +1,99 @@
+//! Snapshots of how much of an arena is in use.
+
+use core::fmt;
+
+use crate::{atomic::Ordering, DynArena};
+
+/// How much of an arena was in use at one moment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct Stats {
+    /// The number of bytes the arena can hold.
+    pub capacity: usize,
+    /// The number of bytes acquired so far, including alignment padding.
+    pub used: usize,
+    /// The number of bytes of `used` that were skipped to align values rather than
+    /// hold them. A large share suggests acquiring values in order of alignment,
+    /// or keeping values of one type together.
+    pub padding: usize,
+    /// The number of values whose destructors the arena will run.
+    pub values: usize,
+    /// The number of times the arena has been reset.
+    pub generation: usize,
+    /// The number of acquisitions that found no room since the arena was created.
+    pub failures: usize,
+    /// The total size in bytes of the acquisitions that found no room, which stops
+    /// at `usize::MAX`.
+    pub failed_bytes: usize,
+    /// The size in bytes of the largest acquisition that found no room. Close to
+    /// `failed_bytes` it points at one absurd request rather than an arena that is a
+    /// little too small.
+    pub largest_failure: usize,
+}
+
+impl Stats {
+    /// The number of bytes that have not been acquired yet.
+    pub fn remaining(&self) -> usize {
+        self.capacity - self.used
+    }
+
+    /// Write the snapshot to `w` as a JSON object, for dashboards and test rigs
+    /// that ingest memory telemetry. With the `serde` feature it serializes the same way.
+    pub fn write_json(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        write!(
+            w,
+            concat!(
+                r#"{{"capacity":{},"used":{},"padding":{},"values":{},"generation":{},"#,
+                r#""failures":{},"failed_bytes":{},"largest_failure":{}}}"#
+            ),
+            self.capacity,
+            self.used,
+            self.padding,
+            self.values,
+            self.generation,
+            self.failures,
+            self.failed_bytes,
+            self.largest_failure
+        )
+    }
+
+    /// The snapshot as a JSON object.
+    #[cfg(feature = "std")]
+    pub fn to_json(&self) -> std::string::String {
+        let mut json = std::string::String::new();
+        // Writing to a String never fails.
+        let _ = self.write_json(&mut json);
+        json
+    }
+}
+
+impl DynArena {
+    /// Take a snapshot of how much of the arena is in use.
+    pub fn stats(&self) -> Stats {
+        Stats {
+            capacity: self.capacity(),
+            used: self.next_free_store_spot.load(Ordering::Acquire),
+            padding: self.padding.load(Ordering::Relaxed),
+            values: self
+                .next_free_drop_spot
+                .load(Ordering::Acquire)
+                .min(self.storage.drop_queue().len()),
+            generation: self.generation(),
+            failures: self.failures.load(Ordering::Relaxed),
+            failed_bytes: self.failed_bytes.load(Ordering::Relaxed),
+            largest_failure: self.largest_failure.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Count an acquisition of `size` bytes that found no room.
+    pub(crate) fn count_failure(&self, size: usize) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        let _ = self
+            .failed_bytes
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bytes| {
+                Some(bytes.saturating_add(size))
+            });
+        self.largest_failure.fetch_max(size, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,94 @@
+//! Thin bindings to the host's virtual memory functions for std builds.
+
+#![allow(dead_code)]
+
+use core::{ffi::c_void, ptr::NonNull};
+use std::io;
+
+#[cfg(target_os = "linux")]
+mod consts {
+    pub const MAP_ANONYMOUS: i32 = 0x20;
+    pub const SC_PAGESIZE: i32 = 30;
+}
+
+#[cfg(target_os = "macos")]
+mod consts {
+    pub const MAP_ANONYMOUS: i32 = 0x1000;
+    pub const SC_PAGESIZE: i32 = 29;
+}
+
+const PROT_NONE: i32 = 0;
+const PROT_READ: i32 = 1;
+const PROT_WRITE: i32 = 2;
+const MAP_PRIVATE: i32 = 2;
+const MAP_FAILED: *mut c_void = core::ptr::without_provenance_mut(!0);
+
+extern "C" {
+    fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, off: i64) -> *mut c_void;
+    fn munmap(addr: *mut c_void, len: usize) -> i32;
+    fn mprotect(addr: *mut c_void, len: usize, prot: i32) -> i32;
+    fn mlock(addr: *const c_void, len: usize) -> i32;
+    fn munlock(addr: *const c_void, len: usize) -> i32;
+    fn sysconf(name: i32) -> i64;
+}
+
+/// The size of a virtual memory page.
+pub fn page_size() -> usize {
+    (unsafe { sysconf(consts::SC_PAGESIZE) }) as usize
+}
+
+/// Map `len` bytes of zeroed, readable and writable memory.
+pub fn map(len: usize) -> io::Result<NonNull<u8>> {
+    let ptr = unsafe {
+        mmap(
+            core::ptr::null_mut(),
+            len,
+            PROT_READ | PROT_WRITE,
+            MAP_PRIVATE | consts::MAP_ANONYMOUS,
+            -1,
+            0,
+        )
+    };
+    if ptr == MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(NonNull::new(ptr.cast()).unwrap())
+}
+
+/// Make `len` bytes at `ptr` inaccessible.
+///
+/// # Safety
+/// The range must be page aligned and within a mapping made by [`map`].
+pub unsafe fn protect_none(ptr: NonNull<u8>, len: usize) -> io::Result<()> {
+    if unsafe { mprotect(ptr.as_ptr().cast(), len, PROT_NONE) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Lock `len` bytes at `ptr` into RAM, so that they are never swapped out.
+///
+/// # Safety
+/// The range must be page aligned and within a mapping made by [`map`].
+pub unsafe fn lock(ptr: NonNull<u8>, len: usize) -> io::Result<()> {
+    if unsafe { mlock(ptr.as_ptr().cast(), len) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Let `len` bytes at `ptr` locked by [`lock`] be swapped out again.
+///
+/// # Safety
+/// The range must be page aligned and within a mapping made by [`map`].
+pub unsafe fn unlock(ptr: NonNull<u8>, len: usize) {
+    unsafe { munlock(ptr.as_ptr().cast(), len) };
+}
+
+/// Unmap `len` bytes at `ptr`.
+///
+/// # Safety
+/// The range must be a mapping made by [`map`] that is no longer referenced.
+pub unsafe fn unmap(ptr: NonNull<u8>, len: usize) {
+    unsafe { munmap(ptr.as_ptr().cast(), len) };
+}
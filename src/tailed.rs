@@ -0,0 +1,166 @@
+//! Dynamically sized structs made of a header and a slice tail.
+//!
+//! The tail is laid out right after the header in the same reservation, as in
+//! a C struct with a flexible array member, so a protocol frame and its payload
+//! take one acquire and one drop slot.
+
+use core::{
+    mem::{align_of, needs_drop, size_of},
+    ptr,
+};
+
+use crate::{slice::Partial, DynArena, TypeInfo};
+
+/// A header of type H followed by a slice of values of type T, laid out as a
+/// `#[repr(C)]` struct with the slice as its last field.
+///
+/// ```
+/// use arena_alloc::{Arena, Tailed};
+///
+/// struct FrameHeader {
+///     kind: u8,
+///     seq: u16,
+/// }
+///
+/// type Frame = Tailed<FrameHeader, u8>;
+///
+/// static ARENA: Arena<256> = Arena::new();
+///
+/// let frame: &Frame = ARENA
+///     .acquire_tailed_copy(FrameHeader { kind: 2, seq: 7 }, b"payload")
+///     .unwrap();
+/// assert_eq!(frame.header.seq, 7);
+/// assert_eq!(&frame.tail, b"payload");
+/// ```
+#[repr(C)]
+pub struct Tailed<H, T> {
+    /// The fixed-size part.
+    pub header: H,
+    /// The variable-length part.
+    pub tail: [T],
+}
+
+impl<H, T> Tailed<H, T> {
+    /// The alignment of the struct.
+    const ALIGN: usize = if align_of::<H>() > align_of::<T>() {
+        align_of::<H>()
+    } else {
+        align_of::<T>()
+    };
+
+    /// The offset of the tail from the start of the struct.
+    const TAIL: usize = size_of::<H>().next_multiple_of(align_of::<T>());
+
+    /// The size of the struct with a tail of `len` values, padding included.
+    fn size(len: usize) -> Option<usize> {
+        size_of::<T>()
+            .checked_mul(len)?
+            .checked_add(Self::TAIL)?
+            .checked_next_multiple_of(Self::ALIGN)
+    }
+
+    /// The number of bytes between the start of the length header kept before the
+    /// struct, if it needs dropping, and the struct itself.
+    const HEADER: usize = size_of::<usize>().next_multiple_of(Self::ALIGN);
+
+    const NEEDS_DROP: bool = needs_drop::<H>() || needs_drop::<T>();
+
+    /// The struct with a tail of `len` values at `ptr`.
+    fn from_raw_parts(ptr: *mut u8, len: usize) -> *mut Self {
+        ptr::slice_from_raw_parts_mut(ptr.cast::<T>(), len) as *mut Self
+    }
+}
+
+impl TypeInfo {
+    /// The info for a `Tailed<H, T>`, whose tail length is kept in a header before it.
+    fn of_tailed<H, T>() -> &'static TypeInfo {
+        const {
+            &TypeInfo {
+                drop_func: |ptr: *mut u8| unsafe {
+                    let len = ptr.cast::<usize>().read();
+                    Tailed::<H, T>::from_raw_parts(ptr.add(Tailed::<H, T>::HEADER), len)
+                        .drop_in_place();
+                },
+                needs_drop: core::mem::needs_drop::<H>() || core::mem::needs_drop::<T>(),
+                child: false,
+                #[cfg(feature = "alloc-info")]
+                size: |ptr| unsafe {
+                    Tailed::<H, T>::size(ptr.cast::<usize>().read()).unwrap_or(0)
+                },
+                #[cfg(feature = "alloc-info")]
+                type_name: core::any::type_name::<Tailed<H, T>>,
+                #[cfg(feature = "alloc-info")]
+                align: if Tailed::<H, T>::ALIGN > align_of::<usize>() {
+                    Tailed::<H, T>::ALIGN
+                } else {
+                    align_of::<usize>()
+                },
+                #[cfg(feature = "alloc-info")]
+                header: Tailed::<H, T>::HEADER,
+                #[cfg(feature = "type-tags")]
+                type_id: None,
+            }
+        }
+    }
+}
+
+impl DynArena {
+    /// acquire a `Tailed` with the given header and a tail of `len` values, each
+    /// initialized with the result of calling `f` with its index.
+    /// If `f` panics, the header and the values it has already returned are dropped.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_tailed<H, T>(
+        &self,
+        header: H,
+        len: usize,
+        mut f: impl FnMut(usize) -> T,
+    ) -> Option<&Tailed<H, T>> {
+        let size = Tailed::<H, T>::size(len)?;
+        let (front, align) = if Tailed::<H, T>::NEEDS_DROP {
+            let align = Tailed::<H, T>::ALIGN.max(align_of::<usize>());
+            (Tailed::<H, T>::HEADER, align)
+        } else {
+            (0, Tailed::<H, T>::ALIGN)
+        };
+        let mut slot = self.claim_drop_slots(usize::from(Tailed::<H, T>::NEEDS_DROP))?;
+        let place = self.reserve(size.checked_add(front)?, align);
+        #[cfg(feature = "record")]
+        self.record_layout(
+            core::any::type_name::<Tailed<H, T>>(),
+            size + front,
+            align,
+            place.is_some(),
+        );
+        let place = place?;
+
+        let base = unsafe { self.store().cast::<u8>().add(place + front) };
+        let start = unsafe { base.add(Tailed::<H, T>::TAIL).cast::<T>() };
+        let mut partial = Partial { start, len: 0 };
+        while partial.len < len {
+            let val = f(partial.len);
+            unsafe { start.add(partial.len).write(val) };
+            partial.len += 1;
+        }
+        core::mem::forget(partial);
+        unsafe { base.cast::<H>().write(header) };
+
+        if Tailed::<H, T>::NEEDS_DROP {
+            unsafe {
+                self.store()
+                    .cast::<u8>()
+                    .add(place)
+                    .cast::<usize>()
+                    .write(len)
+            };
+            slot.fill(place, TypeInfo::of_tailed::<H, T>());
+        }
+
+        Some(unsafe { &*Tailed::from_raw_parts(base, len) })
+    }
+
+    /// acquire a `Tailed` with the given header and a copy of `tail`.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_tailed_copy<H, T: Copy>(&self, header: H, tail: &[T]) -> Option<&Tailed<H, T>> {
+        self.acquire_tailed(header, tail.len(), |i| tail[i])
+    }
+}
@@ -0,0 +1,59 @@
+//! Copies of the whole contents of one arena into another, for stamping out
+//! instances of data that is costly to build, such as a parsed configuration.
+
+use core::ptr;
+
+use crate::{annotate, atomic::Ordering, DynArena};
+
+impl DynArena {
+    /// Copy everything in `template` into this arena, which must be empty, so that
+    /// it holds the same values at the same offsets, e.g. to parse a default
+    /// configuration once and stamp it into the arena of each instance at startup.
+    ///
+    /// The copy is made byte for byte, so values in the template should refer to
+    /// each other by [`Offset`](crate::Offset) rather than by reference, which would
+    /// point back into the template. Only values that need no dropping can be
+    /// copied this way, so the template must hold no others, nor any finalizer.
+    ///
+    /// Returns false, copying nothing, if the template holds something that must be
+    /// dropped, this arena is not empty or has too little room, or the two backing
+    /// stores are not aligned alike for every value in the template.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn stamp_from(&self, template: &mut DynArena) -> bool {
+        let used = *template.next_free_store_spot.get_mut();
+        let drop_queue = unsafe { &*template.storage.drop_queue() };
+        let drops = (*template.next_free_drop_spot.get_mut()).min(drop_queue.len());
+        if *template.must_drop.get_mut() || *template.finalizers.get_mut() != 0 {
+            return false;
+        }
+        let from = template.store().cast::<u8>();
+        let to = self.store().cast::<u8>();
+        // Offsets that are aligned in the template are aligned here if the stores
+        // are as far apart as a multiple of the largest alignment in it.
+        let apart = to.addr().wrapping_sub(from.addr());
+        let align = *template.max_align.get_mut();
+        if apart % align != 0 || used > self.best_effort_capacity() {
+            return false;
+        }
+        let Some(mut slots) = self.claim_drop_slots(drops) else {
+            return false;
+        };
+        if self
+            .next_free_store_spot
+            .compare_exchange(0, used, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return false;
+        }
+        annotate::reserve(to, to, used);
+        unsafe { ptr::copy_nonoverlapping(from, to, used) };
+        self.padding
+            .fetch_add(*template.padding.get_mut(), Ordering::Relaxed);
+        self.max_align.fetch_max(align, Ordering::Relaxed);
+        for dropper in drop_queue[..drops].iter().filter_map(|slot| slot.0) {
+            slots.fill(dropper.place, dropper.info);
+        }
+        self.check_watches(used);
+        true
+    }
+}
@@ -0,0 +1,56 @@
+//! An arena for each thread, for host tools that acquire from many threads at once.
+
+use crate::DynArena;
+
+/// Declare an arena of `$size` bytes for each thread, which [`with_thread_arena`]
+/// lends out, so that threads never contend for one arena. Each thread's arena
+/// is created the first time the thread uses it and drops its values when the
+/// thread exits.
+///
+/// Declare it once, in the binary; using `with_thread_arena` without it fails to link.
+///
+/// ```
+/// use arena_alloc::{thread_local_arena, with_thread_arena};
+///
+/// thread_local_arena!(4096);
+///
+/// let len = with_thread_arena(|arena| arena.concat_strs(&["per-", "thread"]).unwrap().len());
+/// assert_eq!(len, 10);
+/// ```
+#[macro_export]
+macro_rules! thread_local_arena {
+    ($size:expr) => {
+        #[export_name = "__arena_alloc_with_thread_arena"]
+        fn __arena_alloc_with_thread_arena(f: &mut dyn FnMut(&$crate::DynArena)) {
+            ::std::thread_local! {
+                static ARENA: $crate::Arena<{ $size }> = const { $crate::Arena::new() };
+            }
+            ARENA.with(|arena| f(arena));
+        }
+    };
+}
+
+extern "Rust" {
+    // Defined by `thread_local_arena!`.
+    #[link_name = "__arena_alloc_with_thread_arena"]
+    fn with_thread_arena_dyn(f: &mut dyn FnMut(&DynArena));
+}
+
+/// Call `f` with the calling thread's arena declared with [`thread_local_arena!`].
+/// What `f` acquires lives as long as the borrow it is given, so it stays in the
+/// closure; anything kept past it has to be copied out.
+// Inlined so that a program that never calls it does not need the arenas to link.
+#[inline]
+pub fn with_thread_arena<R>(f: impl FnOnce(&DynArena) -> R) -> R {
+    let mut f = Some(f);
+    let mut out = None;
+    unsafe {
+        with_thread_arena_dyn(&mut |arena| {
+            if let Some(f) = f.take() {
+                out = Some(f(arena));
+            }
+        })
+    };
+    // The arena always calls back, `LocalKey::with` panicking if it can not.
+    out.unwrap()
+}
@@ -0,0 +1,212 @@
+//! A map from byte strings to values, with nodes kept in an arena.
+
+use core::cell::Cell;
+
+use crate::DynArena;
+
+struct Node<'a, V> {
+    // The bytes of the key between this node and its parent; empty only for the root.
+    label: Cell<&'a [u8]>,
+    value: Cell<Option<&'a V>>,
+    child: Cell<Option<&'a Node<'a, V>>>,
+    sibling: Cell<Option<&'a Node<'a, V>>>,
+}
+
+impl<'a, V> Node<'a, V> {
+    fn new(label: &'a [u8]) -> Self {
+        Node {
+            label: Cell::new(label),
+            value: Cell::new(None),
+            child: Cell::new(None),
+            sibling: Cell::new(None),
+        }
+    }
+
+    fn children(&self) -> impl Iterator<Item = &'a Node<'a, V>> {
+        core::iter::successors(self.child.get(), |child| child.sibling.get())
+    }
+
+    /// The link to the child whose label starts with `byte`, or to the end of the children.
+    fn child_slot(&self, byte: u8) -> &Cell<Option<&'a Node<'a, V>>> {
+        let mut slot = &self.child;
+        while let Some(child) = slot.get() {
+            if child.label.get()[0] == byte {
+                break;
+            }
+            slot = &child.sibling;
+        }
+        slot
+    }
+}
+
+/// A map from byte strings to values, whose nodes and values are kept in an arena,
+/// e.g. the commands of a command parser or the topic filters of an MQTT broker.
+///
+/// Nodes are split only where keys differ, so a key of any length costs at most
+/// two nodes. The space of a replaced value is only reclaimed when its arena is reset.
+pub struct Trie<'a, V> {
+    arena: &'a DynArena,
+    root: Option<&'a Node<'a, V>>,
+    len: usize,
+}
+
+impl<'a, V> Trie<'a, V> {
+    /// Create a new, empty trie whose nodes are acquired from `arena`.
+    #[must_use]
+    pub const fn new(arena: &'a DynArena) -> Self {
+        Trie {
+            arena,
+            root: None,
+            len: 0,
+        }
+    }
+
+    /// The number of keys in the trie.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the trie has no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// acquire `val` and map `key` to it, returning the value it was mapped to before, if any.
+    /// Gives `val` back if the arena has no room for it or the nodes it needs.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn insert(&mut self, key: &[u8], val: V) -> Result<Option<&'a V>, V> {
+        let arena = self.arena;
+        let mut node = match self.root {
+            Some(root) => root,
+            None => match arena.acquire(Node::new(&[])) {
+                Some(root) => *self.root.insert(root),
+                None => return Err(val),
+            },
+        };
+        let mut rest = key;
+        while let Some(&byte) = rest.first() {
+            let slot = node.child_slot(byte);
+            let Some(child) = slot.get() else {
+                let Some(label) = arena.concat_slices(&[rest]) else {
+                    return Err(val);
+                };
+                let Some(leaf) = arena.acquire(Node::new(label)) else {
+                    return Err(val);
+                };
+                let val = arena.try_acquire(val)?;
+                leaf.value.set(Some(val));
+                slot.set(Some(leaf));
+                self.len += 1;
+                return Ok(None);
+            };
+            let label = child.label.get();
+            let common = label.iter().zip(rest).take_while(|(a, b)| a == b).count();
+            if common < label.len() {
+                // Split the child where the keys part ways.
+                let Some(mid) = arena.acquire(Node::new(&label[..common])) else {
+                    return Err(val);
+                };
+                child.label.set(&label[common..]);
+                mid.sibling.set(child.sibling.take());
+                mid.child.set(Some(child));
+                slot.set(Some(mid));
+                node = mid;
+            } else {
+                node = child;
+            }
+            rest = &rest[common..];
+        }
+        let val = arena.try_acquire(val)?;
+        let old = node.value.replace(Some(val));
+        if old.is_none() {
+            self.len += 1;
+        }
+        Ok(old)
+    }
+
+    /// The node where `key` ends, if it ends where a node does.
+    fn find(&self, key: &[u8]) -> Option<&'a Node<'a, V>> {
+        let mut node = self.root?;
+        let mut rest = key;
+        while let Some(&byte) = rest.first() {
+            node = node.child_slot(byte).get()?;
+            rest = rest.strip_prefix(node.label.get())?;
+        }
+        Some(node)
+    }
+
+    /// The value of `key`, if it is in the trie.
+    pub fn get(&self, key: &[u8]) -> Option<&'a V> {
+        self.find(key)?.value.get()
+    }
+
+    /// The longest key in the trie that `input` starts with, as its length and value,
+    /// e.g. to find the command at the start of a line.
+    pub fn longest_prefix(&self, input: &[u8]) -> Option<(usize, &'a V)> {
+        let mut node = self.root?;
+        let mut rest = input;
+        let mut longest = node.value.get().map(|val| (0, val));
+        while let Some(&byte) = rest.first() {
+            let Some(child) = node.child_slot(byte).get() else {
+                break;
+            };
+            let Some(after) = rest.strip_prefix(child.label.get()) else {
+                break;
+            };
+            node = child;
+            rest = after;
+            if let Some(val) = node.value.get() {
+                longest = Some((input.len() - rest.len(), val));
+            }
+        }
+        longest
+    }
+
+    /// Call `f` with the value of every key that matches `topic` as an MQTT topic filter,
+    /// where `+` matches one level of the topic and a final `#` matches any number of them.
+    pub fn for_each_match(&self, topic: &[u8], mut f: impl FnMut(&'a V)) {
+        if let Some(root) = self.root {
+            match_topic(root, 0, topic, true, false, &mut f);
+        }
+    }
+}
+
+/// Match the rest of `topic` against the filters below byte `i` of the label of `node`.
+/// `level_start` is whether the topic is at the start of a level, and `parent` whether
+/// the topic has ended and only a `/#` that also matches its parent level can still match.
+fn match_topic<'a, V>(
+    node: &'a Node<'a, V>,
+    i: usize,
+    topic: &[u8],
+    level_start: bool,
+    parent: bool,
+    f: &mut impl FnMut(&'a V),
+) {
+    let label = node.label.get();
+    let Some(&byte) = label.get(i) else {
+        if let Some(val) = node.value.get().filter(|_| topic.is_empty() && !parent) {
+            f(val);
+        }
+        for child in node.children() {
+            match_topic(child, 0, topic, level_start, parent, f);
+        }
+        return;
+    };
+    match byte {
+        b'#' if level_start => {
+            if let Some(val) = node.value.get().filter(|_| i + 1 == label.len()) {
+                f(val);
+            }
+        }
+        _ if parent => {}
+        b'+' if level_start => {
+            let level = topic.iter().position(|b| *b == b'/').unwrap_or(topic.len());
+            match_topic(node, i + 1, &topic[level..], false, false, f);
+        }
+        b'/' if topic.is_empty() => match_topic(node, i + 1, topic, true, true, f),
+        _ if topic.first() == Some(&byte) => {
+            match_topic(node, i + 1, &topic[1..], byte == b'/', false, f);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,95 @@
+//! Callbacks for when an arena's usage first crosses a threshold.
+
+use core::ptr;
+
+use crate::{
+    atomic::{AtomicPtr, AtomicUsize, Ordering},
+    DynArena,
+};
+
+/// The number of thresholds an arena can watch.
+pub(crate) const WATCHES: usize = 4;
+
+/// A threshold an arena's usage is watched for.
+pub(crate) struct Watch {
+    /// The number of used bytes that crosses the threshold.
+    bytes: AtomicUsize,
+    /// The `fn()` to call when it is crossed, or null if the watch is free.
+    callback: AtomicPtr<()>,
+}
+
+impl Watch {
+    /// A watch no threshold has been set for.
+    pub(crate) const fn free() -> Self {
+        Watch {
+            bytes: AtomicUsize::new(usize::MAX),
+            callback: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+}
+
+impl DynArena {
+    /// Call `callback` the first time an acquisition brings the arena's usage to
+    /// `percent` percent of its capacity or past it, or right away if it is past
+    /// it already, e.g. at 80 and 95 percent to start shedding best-effort work
+    /// before the arena runs out. Resetting the arena arms the callback again.
+    ///
+    /// The callback runs in whatever context made the acquisition, so it should
+    /// be as short as an interrupt handler. Returns false if the arena already
+    /// watches as many thresholds as it can.
+    pub fn on_usage_threshold(&self, percent: usize, callback: fn()) -> bool {
+        let capacity = self.capacity();
+        let bytes = capacity / 100 * percent + capacity % 100 * percent / 100;
+        for (i, watch) in self.watches.iter().enumerate() {
+            if watch.bytes.load(Ordering::Acquire) != usize::MAX
+                || watch
+                    .callback
+                    .compare_exchange(
+                        ptr::null_mut(),
+                        callback as *mut (),
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    )
+                    .is_err()
+            {
+                continue;
+            }
+            watch.bytes.store(bytes, Ordering::Release);
+            self.armed_watches.fetch_or(1 << i, Ordering::AcqRel);
+            self.check_watches(self.next_free_store_spot.load(Ordering::Acquire));
+            return true;
+        }
+        false
+    }
+
+    /// Call the callback of every armed watch whose threshold `used` bytes cross.
+    pub(crate) fn check_watches(&self, used: usize) {
+        let armed = self.armed_watches.load(Ordering::Acquire);
+        if armed == 0 {
+            return;
+        }
+        for (i, watch) in self.watches.iter().enumerate() {
+            let bit = 1 << i;
+            if armed & bit == 0 || used < watch.bytes.load(Ordering::Acquire) {
+                continue;
+            }
+            if self.armed_watches.fetch_and(!bit, Ordering::AcqRel) & bit != 0 {
+                let callback = watch.callback.load(Ordering::Acquire);
+                // Only ever stored from a `fn()` by `on_usage_threshold`.
+                let callback = unsafe { core::mem::transmute::<*mut (), fn()>(callback) };
+                callback();
+            }
+        }
+    }
+
+    /// Arm every watch again, as the arena is rewound.
+    pub(crate) fn rearm_watches(&self) {
+        let mut armed = 0;
+        for (i, watch) in self.watches.iter().enumerate() {
+            if watch.bytes.load(Ordering::Acquire) != usize::MAX {
+                armed |= 1 << i;
+            }
+        }
+        self.armed_watches.store(armed, Ordering::Release);
+    }
+}
@@ -0,0 +1,106 @@
+//! Arenas over a fixed window of physical or device memory, for kernels and
+//! bootloaders to serve early allocations from before a real allocator exists.
+
+use core::{
+    mem::{align_of, size_of},
+    ops::Deref,
+    ptr::{self, NonNull},
+    slice,
+};
+
+use crate::{annotate, atomic::Ordering, region::Region, DropSlot, DynArena, RegionArena};
+
+impl RegionArena<'static> {
+    /// Create a new arena over the `len` bytes at address `addr`, e.g. a physical
+    /// window the firmware reports as free, mapped one to one during early boot.
+    ///
+    /// The drop queue, with room for `drop_slots` entries, takes the start of the
+    /// window, and values go in the rest. Returns None if the window is too small
+    /// to hold the drop queue.
+    ///
+    /// # Safety
+    /// The `len` bytes at `addr` must be valid for reads and writes, and used by
+    /// nothing else, for as long as the arena or anything acquired from it lives.
+    pub unsafe fn at_address(addr: usize, len: usize, drop_slots: usize) -> Option<Self> {
+        let front = addr.next_multiple_of(align_of::<DropSlot>()) - addr;
+        let queue = size_of::<DropSlot>().checked_mul(drop_slots)?;
+        let store_len = len.checked_sub(front.checked_add(queue)?)?;
+        let window = ptr::with_exposed_provenance_mut::<u8>(addr);
+        let drop_queue = unsafe { window.add(front) }.cast::<DropSlot>();
+        for i in 0..drop_slots {
+            unsafe { drop_queue.add(i).write(DropSlot::EMPTY) };
+        }
+        let store = unsafe { window.add(front + queue) };
+        Some(RegionArena::with_region(Region {
+            store: ptr::slice_from_raw_parts_mut(store, store_len),
+            drop_queue: ptr::slice_from_raw_parts_mut(drop_queue, drop_slots),
+        }))
+    }
+
+    /// Retire the arena once early allocations are done, handing the bytes it has
+    /// not acquired, zeroed, to whatever takes over, e.g. the main allocator, along
+    /// with the values it holds, which stay where they are until the [`DropList`] drops.
+    pub fn into_remaining(mut self) -> (&'static mut [u8], DropList) {
+        let store = self.arena.storage.store;
+        let used = (*self.next_free_store_spot.get_mut()).min(store.len());
+        let rest = unsafe { store.cast::<u8>().add(used) };
+        annotate::expose(rest, store.len() - used);
+        self.arena.storage.store = ptr::slice_from_raw_parts_mut(store.cast(), used);
+        unsafe { rest.write_bytes(0, store.len() - used) };
+        let rest = unsafe { slice::from_raw_parts_mut(rest, store.len() - used) };
+        (rest, DropList(self))
+    }
+}
+
+/// The values of an arena retired with [`RegionArena::into_remaining`], which are
+/// dropped when it is, as the arena would have dropped them.
+///
+/// Derefs to the retired arena, to reach the values by [`Offset`](crate::Offset).
+/// It has no room left, so nothing more can be acquired from it. Forget the list
+/// to keep the values for the rest of the program.
+pub struct DropList(RegionArena<'static>);
+
+impl Deref for DropList {
+    type Target = DynArena;
+
+    fn deref(&self) -> &DynArena {
+        &self.0
+    }
+}
+
+impl DropList {
+    /// Whether any of the values needs dropping, so that the list must be kept
+    /// until they are no longer used.
+    pub fn must_drop(&self) -> bool {
+        self.0.must_drop.load(Ordering::Acquire)
+    }
+
+    /// Drop the values, e.g. once whatever early boot set up with them is torn down.
+    pub fn run(self) {
+        drop(self);
+    }
+}
+
+impl DynArena {
+    /// acquire room for a value of type T, initialized with the given value by a
+    /// volatile write, as a pointer for the caller to access with volatile reads
+    /// and writes, e.g. a descriptor a device reads from an arena over its memory.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_volatile<T: Copy>(&self, val: T) -> Option<NonNull<T>> {
+        let (_, ptr) = self.get_ptr_place::<T>()?;
+        let ptr = NonNull::from(ptr).cast::<T>();
+        unsafe { ptr.write_volatile(val) };
+        Some(ptr)
+    }
+
+    /// acquire room for a slice of `len` values of type T, each initialized with
+    /// the given value by a volatile write, as a pointer to its first element.
+    #[cfg_attr(feature = "record", track_caller)]
+    pub fn acquire_volatile_slice<T: Copy>(&self, len: usize, val: T) -> Option<NonNull<T>> {
+        let (_, start) = self.reserve_slice::<T>(len)?;
+        for i in 0..len {
+            unsafe { start.add(i).write_volatile(val) };
+        }
+        NonNull::new(start)
+    }
+}
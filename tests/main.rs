@@ -69,7 +69,15 @@ impl<'b, T> Init for CdllNode<'b, T> {
 
 #[test]
 fn test_main() {
-    let arena: Arc<Arena<40000>> = Arena::new().into();
+    // Room for a canary after every node, with the `canaries` feature. The arena is
+    // built on a thread with a larger stack, as its drop queue grows with the
+    // `lifetimes` and `call-sites` features past what the test thread has room for.
+    let arena: Arc<Arena<41000>> = std::thread::Builder::new()
+        .stack_size(32 << 20)
+        .spawn(|| Arena::new().into())
+        .unwrap()
+        .join()
+        .unwrap();
     let mut v = Vec::new();
     for _ in 0..10 {
         let arena = Arc::clone(&arena);
@@ -91,4 +99,62 @@ fn test_main() {
         let r = h.join();
         assert!(r.unwrap() == 0);
     }
-}
\ No newline at end of file
+}
+#[cfg(feature = "derive")]
+#[test]
+fn test_derive_clone_in() {
+    use arena_alloc::CloneIn;
+
+    #[derive(CloneIn)]
+    struct Config<'a> {
+        name: &'a str,
+        port: Option<u16>,
+        routes: &'a [Route<'a>],
+    }
+
+    #[derive(CloneIn)]
+    enum Route<'a> {
+        Static(&'a str, u8),
+        Proxy { upstream: &'a str },
+        Drop,
+    }
+
+    #[derive(CloneIn)]
+    struct Tagged<T>(T, bool);
+
+    let long = Arena::<512>::new();
+    let kept = {
+        let scratch = Arena::<512>::new();
+        let routes = scratch
+            .acquire_slice_fill_with(3, |i| match i {
+                0 => Route::Static("/index.html", 1),
+                1 => Route::Proxy { upstream: "api:80" },
+                _ => Route::Drop,
+            })
+            .unwrap();
+        let name = String::from("gateway");
+        let config = Config {
+            name: &name,
+            port: Some(8080),
+            routes,
+        };
+        config.clone_in(&long).unwrap()
+    };
+    assert!(kept.name == "gateway" && kept.port == Some(8080));
+    assert!(matches!(kept.routes[0], Route::Static("/index.html", 1)));
+    assert!(matches!(
+        kept.routes[1],
+        Route::Proxy { upstream: "api:80" }
+    ));
+    assert!(matches!(kept.routes[2], Route::Drop));
+    assert!(Tagged(7u32, true)
+        .clone_in(&long)
+        .is_some_and(|t| t.0 == 7 && t.1));
+    assert!(Config {
+        name: "x",
+        port: None,
+        routes: &[]
+    }
+    .clone_in(&Arena::<0>::new())
+    .is_none());
+}